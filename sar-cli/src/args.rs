@@ -0,0 +1,35 @@
+//! Value parsers shared between subcommands
+
+use sar_core::Color;
+use std::ops::Range;
+
+/// The format of machine-readable reports
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Human-readable lines
+    Text,
+    /// A JSON document
+    Json,
+}
+
+/// Parses a color in `rrggbb` or `rrggbbaa` hex notation
+pub fn parse_color(value: &str) -> Result<Color, String> {
+    Color::from_hex(value).ok_or_else(|| format!("expected rrggbb but got: {value}"))
+}
+
+/// Parses a layer range as `START..END`, `START..` or a single index
+pub fn parse_layer_range(value: &str) -> Result<Range<usize>, String> {
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<usize>()
+            .map_err(|e| format!("invalid layer index: {e}"))
+    };
+    match value.split_once("..") {
+        Some((start, "")) => Ok(parse(start)?..usize::MAX),
+        Some((start, end)) => Ok(parse(start)?..parse(end)?),
+        None => {
+            let index = parse(value)?;
+            Ok(index..index + 1)
+        }
+    }
+}
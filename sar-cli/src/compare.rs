@@ -0,0 +1,88 @@
+use anyhow::{Context, bail};
+use clap::ValueEnum;
+use sar_core::{
+    SymbolArtDrawer,
+    analysis::{CompareMode, compare},
+};
+use std::path::Path;
+use tokio::fs;
+
+#[derive(clap::Args, Debug)]
+pub struct CompareArgs {
+    /// Path to the original SAR file
+    before: String,
+    /// Path to the edited SAR file
+    after: String,
+    /// Path to write the comparison image
+    #[arg(short, long)]
+    output: Option<String>,
+    /// How the comparison image shows the two renders
+    #[arg(long, value_enum, default_value_t = ModeArg::Highlight)]
+    mode: ModeArg,
+    /// Scale of the renders
+    #[arg(long, default_value_t = 1.0)]
+    scale: f32,
+    /// Exit with an error when more than this percentage of pixels changed
+    #[arg(long, default_value_t = 0.0)]
+    threshold: f32,
+    /// Overwrite existing files
+    #[arg(long, default_value_t = false)]
+    overwrite: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ModeArg {
+    Highlight,
+    SideBySide,
+    OnionSkin,
+}
+
+pub async fn run(args: CompareArgs) -> Result<(), anyhow::Error> {
+    if let Some(output) = &args.output
+        && Path::new(output).exists()
+        && !args.overwrite
+    {
+        bail!("output_file already exists: {output}")
+    }
+
+    let mut arts = Vec::with_capacity(2);
+    for path in [&args.before, &args.after] {
+        let bytes = fs::read(path)
+            .await
+            .with_context(|| format!("failed to read: {path}"))?;
+        arts.push(sar_core::parse(bytes).with_context(|| format!("failed to parse: {path}"))?);
+    }
+
+    let mode = match args.mode {
+        ModeArg::Highlight => CompareMode::Highlight,
+        ModeArg::SideBySide => CompareMode::SideBySide,
+        ModeArg::OnionSkin => CompareMode::OnionSkin,
+    };
+    let comparison = compare(
+        &SymbolArtDrawer::new(),
+        &arts[0],
+        &arts[1],
+        args.scale,
+        mode,
+    )?;
+    if let Some(output) = &args.output {
+        comparison
+            .image
+            .save(output)
+            .with_context(|| format!("failed to write: {output}"))?;
+    }
+
+    let percentage = comparison.changed_ratio() * 100.0;
+    println!(
+        "changed: {} of {} pixels ({percentage:.2}%), max delta {}",
+        comparison.changed_pixels, comparison.total_pixels, comparison.max_delta
+    );
+    if percentage > args.threshold {
+        bail!(
+            "{percentage:.2}% of pixels changed, above the threshold of {}%",
+            args.threshold
+        )
+    }
+
+    Ok(())
+}
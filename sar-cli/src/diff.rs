@@ -0,0 +1,52 @@
+use anyhow::Context;
+use sar_core::diff::{LayerDiff, diff};
+use tokio::fs;
+
+use crate::args::Format;
+
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// Path to the original SAR file
+    before: String,
+    /// Path to the edited SAR file
+    after: String,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+pub async fn run(args: DiffArgs) -> Result<(), anyhow::Error> {
+    let mut arts = Vec::with_capacity(2);
+    for path in [&args.before, &args.after] {
+        let bytes = fs::read(path)
+            .await
+            .with_context(|| format!("failed to read: {path}"))?;
+        arts.push(sar_core::parse(bytes).with_context(|| format!("failed to parse: {path}"))?);
+    }
+    let diff = diff(&arts[0], &arts[1]);
+
+    match args.format {
+        Format::Text => {
+            for change in &diff.metadata {
+                println!("{change}");
+            }
+            for layer in &diff.layers {
+                println!("{layer}");
+            }
+
+            let count =
+                |kind: fn(&LayerDiff) -> bool| diff.layers.iter().filter(|l| kind(l)).count();
+            println!(
+                "{} added, {} removed, {} moved, {} changed, {} unchanged",
+                count(|l| matches!(l, LayerDiff::Added { .. })),
+                count(|l| matches!(l, LayerDiff::Removed { .. })),
+                count(|l| matches!(l, LayerDiff::Moved { .. })),
+                count(|l| matches!(l, LayerDiff::Changed { .. })),
+                diff.unchanged
+            );
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+    }
+
+    Ok(())
+}
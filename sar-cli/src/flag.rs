@@ -0,0 +1,80 @@
+use anyhow::{Context, bail};
+use clap::ValueEnum;
+use sar_core::{Compression, Position, Rect, edit::Fit};
+use std::path::Path;
+
+#[derive(clap::Args, Debug)]
+pub struct FlagArgs {
+    /// Path to the SAR file to convert
+    input: String,
+    /// Path to the output SAR file
+    #[arg(short, long)]
+    output: String,
+    /// How the content is fitted into the flag
+    #[arg(long, value_enum, default_value_t = FitArg::Contain)]
+    fit: FitArg,
+    /// Region to fit into the flag as x0,y0,x1,y1, overriding --fit
+    #[arg(long, value_parser = parse_region)]
+    crop: Option<Rect>,
+    /// Convert a flag back into a standard SymbolArt instead
+    #[arg(long, default_value_t = false)]
+    to_standard: bool,
+    /// Scale of the flag within the standard canvas when converting back
+    #[arg(long, default_value_t = 3.0)]
+    scale: f32,
+    /// Overwrite existing files
+    #[arg(long, default_value_t = false)]
+    overwrite: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FitArg {
+    Contain,
+    Cover,
+}
+
+pub async fn run(args: FlagArgs) -> Result<(), anyhow::Error> {
+    let output = Path::new(&args.output);
+    if output.exists() && !args.overwrite {
+        bail!("output_file already exists: {}", output.to_string_lossy())
+    }
+
+    let bytes = tokio::fs::read(&args.input)
+        .await
+        .with_context(|| format!("failed to read: {}", args.input))?;
+    let sa = sar_core::parse(bytes)?;
+
+    let converted = if args.to_standard {
+        sar_core::edit::to_standard(&sa, args.scale)?
+    } else {
+        let fit = match (args.crop, args.fit) {
+            (Some(region), _) => Fit::Crop(region),
+            (None, FitArg::Contain) => Fit::Contain,
+            (None, FitArg::Cover) => Fit::Cover,
+        };
+        sar_core::edit::to_team_flag(&sa, fit)?
+    };
+
+    let bytes = sar_core::encode(&converted, Compression::Compressed)?;
+    tokio::fs::write(output, bytes).await?;
+
+    Ok(())
+}
+
+fn parse_region(value: &str) -> Result<Rect, String> {
+    let values = value
+        .split(',')
+        .map(|v| {
+            v.trim()
+                .parse::<u8>()
+                .map_err(|e| format!("invalid coordinate: {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match values[..] {
+        [x0, y0, x1, y1] if x0 <= x1 && y0 <= y1 => Ok(Rect::new(
+            Position { x: x0, y: y0 },
+            Position { x: x1, y: y1 },
+        )),
+        _ => Err(format!("expected x0,y0,x1,y1 but got: {value}")),
+    }
+}
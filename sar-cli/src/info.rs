@@ -0,0 +1,156 @@
+use anyhow::Context;
+use sar_core::{
+    Canvas, Color, SymbolArt, SymbolArtDrawer, SymbolArtLayer,
+    analysis::{PixelColor, palette, visibility},
+    author::Registry,
+    fingerprint,
+};
+use std::cell::LazyCell;
+
+#[derive(clap::Args, Debug)]
+pub struct InfoArgs {
+    /// Path to the SAR file to inspect
+    input: String,
+    /// Show the colors used by the layers and the rendered image
+    #[arg(long, default_value_t = false)]
+    colors: bool,
+    /// Number of dominant colors to extract
+    #[arg(long, default_value_t = 8)]
+    dominant: usize,
+    /// Maximum number of colors listed per section
+    #[arg(long, default_value_t = 16)]
+    limit: usize,
+    /// Path to an author registry file mapping author IDs to names
+    #[arg(long)]
+    authors: Option<String>,
+    /// Show how many pixels of each layer stay visible, least visible first
+    #[arg(long, default_value_t = false)]
+    visibility: bool,
+    /// Path to write a PNG heatmap of how many layers stack at each pixel
+    #[arg(long)]
+    heatmap: Option<String>,
+}
+
+pub async fn run(args: InfoArgs) -> Result<(), anyhow::Error> {
+    let bytes = tokio::fs::read(&args.input)
+        .await
+        .with_context(|| format!("failed to read: {}", args.input))?;
+    let cache_name = fingerprint::cache_name(&bytes)?;
+    let sa = sar_core::parse(bytes)?;
+    let registry = match &args.authors {
+        Some(path) => tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("failed to read: {path}"))?
+            .parse()?,
+        None => Registry::new(),
+    };
+
+    let layers = sa.layers();
+    let hidden = layers.iter().filter(|layer| layer.is_hidden()).count();
+    let canvas = match sa.canvas() {
+        Some(canvas) => {
+            let name = match canvas {
+                Canvas::Standard => "standard",
+                Canvas::TeamFlag => "alliance flag",
+            };
+            format!("{name} ({}x{})", canvas.width(), canvas.height())
+        }
+        None => "unknown".to_string(),
+    };
+    println!("name:         {}", sa.name());
+    println!("author:       {}", registry.display(sa.author_id()));
+    println!("canvas:       {canvas}");
+    println!("layers:       {} ({hidden} hidden)", layers.len());
+    println!("sound effect: {}", sa.sound_effect());
+    println!("cache name:   {cache_name}");
+    println!("content hash: {}", fingerprint::content_hash(&sa));
+
+    // Loading the symbol sheets is only worth it when something is rendered
+    let drawer = LazyCell::new(SymbolArtDrawer::new);
+    if args.colors {
+        let palette = palette(&drawer, &sa, args.dominant)?;
+
+        println!();
+        println!("layer colors: {}", palette.layer_colors.len());
+        for color in palette.layer_colors.iter().take(args.limit) {
+            println!(
+                "  {} #{} a={:<3} {:>4} layers",
+                swatch(&color.color),
+                color.color.to_hex(),
+                color.color.a,
+                color.layers
+            );
+        }
+        print_truncated(palette.layer_colors.len(), args.limit);
+
+        println!();
+        println!("rendered colors: {}", palette.pixel_colors.len());
+        print_shares(&palette.pixel_colors, args.limit);
+
+        println!();
+        println!("dominant colors:");
+        print_shares(&palette.dominant_colors, args.limit);
+    }
+
+    if args.visibility || args.heatmap.is_some() {
+        let visibility = visibility(&drawer, &sa)?;
+
+        if args.visibility {
+            let mut drawn = visibility
+                .layers
+                .iter()
+                .enumerate()
+                .filter_map(|(i, layer)| layer.map(|layer| (i, layer)))
+                .collect::<Vec<_>>();
+            drawn.sort_by_key(|(i, layer)| (layer.visible, *i));
+            let unseen = drawn.iter().filter(|(_, layer)| layer.visible == 0).count();
+
+            println!();
+            println!(
+                "visibility:   {unseen} of {} drawn layers never show",
+                drawn.len()
+            );
+            println!("overdraw:     up to {} layers", visibility.max_overdraw());
+            for (i, layer) in drawn.iter().take(args.limit) {
+                println!(
+                    "  layer {i:>3} {:>6} of {:>6} px visible",
+                    layer.visible, layer.covered
+                );
+            }
+            print_truncated(drawn.len(), args.limit);
+        }
+
+        if let Some(path) = &args.heatmap {
+            visibility
+                .heatmap()
+                .save(path)
+                .with_context(|| format!("failed to write: {path}"))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn print_shares(colors: &[PixelColor], limit: usize) {
+    for color in colors.iter().take(limit) {
+        println!(
+            "  {} #{} {:>6.2}% {:>7} px",
+            swatch(&color.color),
+            color.color.to_hex(),
+            color.share * 100.0,
+            color.pixels
+        );
+    }
+    print_truncated(colors.len(), limit);
+}
+
+fn print_truncated(len: usize, limit: usize) {
+    if len > limit {
+        println!("  ... and {} more", len - limit);
+    }
+}
+
+/// Formats a block of the color with 24-bit ANSI escape codes
+fn swatch(color: &Color) -> String {
+    format!("\x1b[48;2;{};{};{}m    \x1b[0m", color.r, color.g, color.b)
+}
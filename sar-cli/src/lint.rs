@@ -0,0 +1,81 @@
+use anyhow::{Context, bail};
+use sar_core::lint::{Finding, Severity, lint_file};
+use std::path::Path;
+use tokio::fs;
+
+use crate::args::Format;
+use crate::rename::sar_files;
+
+#[derive(clap::Args, Debug)]
+pub struct LintArgs {
+    /// Path to the SAR file or directory
+    input: String,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Exit with an error when a finding is at least this severe: info, warning or error
+    #[arg(long, default_value = "error", value_parser = parse_severity)]
+    fail_on: Severity,
+    /// Only report findings at least this severe: info, warning or error
+    #[arg(long, default_value = "info", value_parser = parse_severity)]
+    min_severity: Severity,
+}
+
+#[derive(serde::Serialize)]
+struct Report {
+    file: String,
+    findings: Vec<Finding>,
+}
+
+pub async fn run(args: LintArgs) -> Result<(), anyhow::Error> {
+    let input = Path::new(&args.input);
+    let files = if input.is_dir() {
+        sar_files(input).await?
+    } else {
+        vec![input.to_path_buf()]
+    };
+
+    let mut reports = Vec::with_capacity(files.len());
+    for file in &files {
+        let bytes = fs::read(file)
+            .await
+            .with_context(|| format!("failed to read: {}", file.to_string_lossy()))?;
+        reports.push(Report {
+            file: file.to_string_lossy().to_string(),
+            findings: lint_file(bytes),
+        });
+    }
+
+    // Failures count every finding, whatever the minimum severity to report
+    let failures = reports
+        .iter()
+        .flat_map(|report| &report.findings)
+        .filter(|finding| finding.severity >= args.fail_on)
+        .count();
+    for report in &mut reports {
+        report
+            .findings
+            .retain(|finding| finding.severity >= args.min_severity);
+    }
+
+    match args.format {
+        Format::Text => {
+            for report in &reports {
+                for finding in &report.findings {
+                    println!("{}: {finding}", report.file);
+                }
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&reports)?),
+    }
+
+    if failures > 0 {
+        bail!("{failures} findings at or above {}", args.fail_on)
+    }
+
+    Ok(())
+}
+
+fn parse_severity(value: &str) -> Result<Severity, String> {
+    value.parse().map_err(|e| format!("{e}"))
+}
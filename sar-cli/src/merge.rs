@@ -0,0 +1,60 @@
+use anyhow::{Context, bail};
+use sar_core::{Compression, edit::Placement};
+use std::path::Path;
+
+#[derive(clap::Args, Debug)]
+pub struct MergeArgs {
+    /// Path to the base SAR file
+    base: String,
+    /// Path to the SAR file placed on the base
+    other: String,
+    /// Offset of the second SAR file as x,y
+    #[arg(long, default_value = "0,0", value_parser = parse_offset, allow_hyphen_values = true)]
+    offset: (i32, i32),
+    /// Place the second SAR file under the base instead of over it
+    #[arg(long, default_value_t = false)]
+    under: bool,
+    /// Path to the output SAR file
+    #[arg(short, long)]
+    output: String,
+    /// Overwrite existing files
+    #[arg(long, default_value_t = false)]
+    overwrite: bool,
+}
+
+pub async fn run(args: MergeArgs) -> Result<(), anyhow::Error> {
+    let output = Path::new(&args.output);
+    if output.exists() && !args.overwrite {
+        bail!("output_file already exists: {}", output.to_string_lossy())
+    }
+
+    let base = read(&args.base).await?;
+    let other = read(&args.other).await?;
+    let placement = if args.under {
+        Placement::Under
+    } else {
+        Placement::Over
+    };
+
+    let merged = sar_core::edit::merge(&base, &other, args.offset, placement)?;
+    let bytes = sar_core::encode(&merged, Compression::Compressed)?;
+    tokio::fs::write(output, bytes).await?;
+
+    Ok(())
+}
+
+async fn read(path: &str) -> Result<sar_core::Payload, anyhow::Error> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("failed to read: {path}"))?;
+    sar_core::parse(bytes).with_context(|| format!("failed to parse: {path}"))
+}
+
+fn parse_offset(value: &str) -> Result<(i32, i32), String> {
+    let (x, y) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected x,y but got: {value}"))?;
+    let x = x.trim().parse().map_err(|e| format!("invalid x: {e}"))?;
+    let y = y.trim().parse().map_err(|e| format!("invalid y: {e}"))?;
+    Ok((x, y))
+}
@@ -0,0 +1,107 @@
+use anyhow::{Context, bail};
+use sar_core::{Compression, SymbolArt, SymbolArtDrawer, edit::optimize};
+use std::path::Path;
+use tokio::fs;
+
+use crate::rename::sar_files;
+
+#[derive(clap::Args, Debug)]
+pub struct OptimizeArgs {
+    /// Path to the SAR file or directory
+    input: String,
+    /// Path to the output file, or directory when the input is a directory
+    #[arg(short, long, required_unless_present = "in_place")]
+    output: Option<String>,
+    /// Rewrite the input files instead of writing new ones
+    #[arg(long, default_value_t = false, conflicts_with = "output")]
+    in_place: bool,
+    /// Also remove hidden layers
+    #[arg(long, default_value_t = false)]
+    remove_hidden: bool,
+    /// List every removed layer with the reason
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+    /// Overwrite existing files
+    #[arg(long, default_value_t = false)]
+    overwrite: bool,
+}
+
+pub async fn run(args: OptimizeArgs) -> Result<(), anyhow::Error> {
+    let input = Path::new(&args.input);
+    let output = args.output.as_deref().map(Path::new);
+    // Compositing in a single chunk keeps the rounding independent of the layer
+    // count, so the check compares the images as composited in layer order
+    let drawer = SymbolArtDrawer::new().with_chunk_size(usize::MAX);
+
+    if !input.is_dir() {
+        let output = output.unwrap_or(input);
+        optimize_file(input, output, &drawer, &args).await?;
+        return Ok(());
+    }
+
+    if let Some(output) = output {
+        if output.is_file() {
+            bail!("output_path already exists: {}", output.to_string_lossy())
+        }
+        if !output.exists() {
+            fs::create_dir(output).await?;
+        }
+    }
+
+    let mut total = 0;
+    for input_path in sar_files(input).await? {
+        let output_path = match output {
+            Some(output) => output.join(input_path.file_name().unwrap()),
+            None => input_path.clone(),
+        };
+
+        match optimize_file(&input_path, &output_path, &drawer, &args).await {
+            Ok(saved) => total += saved,
+            Err(e) => eprintln!(
+                "failed to optimize: {}: {}",
+                input_path.to_string_lossy(),
+                e
+            ),
+        }
+    }
+    println!("{total} layers saved in total");
+
+    Ok(())
+}
+
+async fn optimize_file(
+    input: &Path,
+    output: &Path,
+    drawer: &SymbolArtDrawer,
+    args: &OptimizeArgs,
+) -> anyhow::Result<usize> {
+    if output != input && output.exists() && !args.overwrite {
+        bail!("output_file already exists: {}", output.to_string_lossy())
+    }
+
+    let bytes = fs::read(input)
+        .await
+        .with_context(|| format!("failed to read: {}", input.to_string_lossy()))?;
+    let mut sa = sar_core::parse(bytes)?;
+    let layers = sa.layers().len();
+    let optimization = optimize(&mut sa, drawer, args.remove_hidden)?;
+
+    println!(
+        "{}: {} of {layers} layers saved",
+        input.to_string_lossy(),
+        optimization.layers_saved()
+    );
+    if args.verbose {
+        for (layer, removal) in &optimization.removed {
+            println!("  layer {layer}: {removal}");
+        }
+    }
+
+    // Files without any change are left untouched when rewritten in place
+    if optimization.layers_saved() > 0 || output != input {
+        let bytes = sar_core::encode(&sa, Compression::Compressed)?;
+        fs::write(output, bytes).await?;
+    }
+
+    Ok(optimization.layers_saved())
+}
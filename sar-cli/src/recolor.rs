@@ -0,0 +1,143 @@
+use anyhow::{Context, bail};
+use sar_core::{
+    Color, ColorSpace, Compression, SymbolArtDrawer,
+    edit::{Recolor, recolor, reduce_palette},
+    query::Predicate,
+};
+use std::{ops::Range, path::Path};
+
+use crate::args::{parse_color, parse_layer_range};
+
+#[derive(clap::Args, Debug)]
+pub struct RecolorArgs {
+    /// Path to the SAR file to recolor
+    input: String,
+    /// Path to the output SAR file
+    #[arg(short, long)]
+    output: String,
+    /// Replace a color with another as FROM=TO, e.g. ff0000=00ff00
+    #[arg(long, value_parser = parse_mapping)]
+    map: Vec<(Color, Color)>,
+    /// Shift the hue by degrees
+    #[arg(long, allow_hyphen_values = true)]
+    hue: Option<f32>,
+    /// Shift the saturation by an amount between -1 and 1
+    #[arg(long, allow_hyphen_values = true)]
+    saturation: Option<f32>,
+    /// Shift the lightness by an amount between -1 and 1
+    #[arg(long, allow_hyphen_values = true)]
+    lightness: Option<f32>,
+    /// Tint toward a color as COLOR:AMOUNT, e.g. 0000ff:0.5
+    #[arg(long, value_parser = parse_tint)]
+    tint: Option<(Color, f32)>,
+    /// Map every color onto the closest color of a comma separated palette
+    #[arg(long, value_delimiter = ',', value_parser = parse_color)]
+    palette: Vec<Color>,
+    /// Measure palette distances perceptually (CIELAB) instead of in sRGB
+    #[arg(long, default_value_t = false)]
+    perceptual: bool,
+    /// Reduce the layer colors to at most N representatives, after the other operations
+    #[arg(long, value_name = "N")]
+    reduce: Option<usize>,
+    /// Only recolor the layers in the range, e.g. 10..40
+    #[arg(long, value_parser = parse_layer_range)]
+    layers: Option<Range<usize>>,
+    /// Overwrite existing files
+    #[arg(long, default_value_t = false)]
+    overwrite: bool,
+}
+
+impl RecolorArgs {
+    /// Collects the requested operations in the order they are applied
+    fn operations(&self) -> Vec<Recolor> {
+        let mut operations = Vec::new();
+        if !self.map.is_empty() {
+            operations.push(Recolor::Map(self.map.clone()));
+        }
+        if self.hue.is_some() || self.saturation.is_some() || self.lightness.is_some() {
+            operations.push(Recolor::Shift {
+                hue: self.hue.unwrap_or(0.0),
+                saturation: self.saturation.unwrap_or(0.0),
+                lightness: self.lightness.unwrap_or(0.0),
+            });
+        }
+        if let Some((color, amount)) = self.tint {
+            operations.push(Recolor::Tint { color, amount });
+        }
+        if !self.palette.is_empty() {
+            operations.push(Recolor::Palette {
+                colors: self.palette.clone(),
+                space: if self.perceptual {
+                    ColorSpace::Lab
+                } else {
+                    ColorSpace::Srgb
+                },
+            });
+        }
+        operations
+    }
+}
+
+pub async fn run(args: RecolorArgs) -> Result<(), anyhow::Error> {
+    let output = Path::new(&args.output);
+    if output.exists() && !args.overwrite {
+        bail!("output_file already exists: {}", output.to_string_lossy())
+    }
+    let operations = args.operations();
+    if operations.is_empty() && args.reduce.is_none() {
+        bail!("no recolor operation given")
+    }
+    if args.reduce.is_some() && args.layers.is_some() {
+        bail!("--reduce applies to every layer and cannot be combined with --layers")
+    }
+
+    let bytes = tokio::fs::read(&args.input)
+        .await
+        .with_context(|| format!("failed to read: {}", args.input))?;
+    let mut sa = sar_core::parse(bytes)?;
+
+    let selection = args.layers.clone().map(Predicate::index_range);
+    for operation in &operations {
+        let changed = recolor(&mut sa, operation, selection.as_ref());
+        let name = match operation {
+            Recolor::Map(_) => "map",
+            Recolor::Shift { .. } => "shift",
+            Recolor::Tint { .. } => "tint",
+            Recolor::Palette { .. } => "palette",
+        };
+        println!("{name}: {changed} layers changed");
+    }
+    if let Some(colors) = args.reduce {
+        let reduction = reduce_palette(&mut sa, &SymbolArtDrawer::new(), colors)?;
+        println!(
+            "reduce: {} layers changed, {} colors, mean error {:.2}, max error {:.2}",
+            reduction.layers_changed,
+            reduction.colors.len(),
+            reduction.mean_error,
+            reduction.max_error
+        );
+    }
+
+    let bytes = sar_core::encode(&sa, Compression::Compressed)?;
+    tokio::fs::write(output, bytes).await?;
+
+    Ok(())
+}
+
+fn parse_mapping(value: &str) -> Result<(Color, Color), String> {
+    let (from, to) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected FROM=TO but got: {value}"))?;
+    Ok((parse_color(from)?, parse_color(to)?))
+}
+
+fn parse_tint(value: &str) -> Result<(Color, f32), String> {
+    let (color, amount) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected COLOR:AMOUNT but got: {value}"))?;
+    let amount = amount
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid amount: {e}"))?;
+    Ok((parse_color(color)?, amount))
+}
@@ -0,0 +1,70 @@
+use anyhow::Context;
+use sar_core::fingerprint;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio_stream::{StreamExt, wrappers::ReadDirStream};
+
+#[derive(clap::Args, Debug)]
+pub struct RenameArgs {
+    /// Path to the SAR file or directory
+    input: String,
+    /// Print the new names without renaming anything
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+}
+
+pub async fn run(args: RenameArgs) -> Result<(), anyhow::Error> {
+    let input = Path::new(&args.input);
+    let files = if input.is_dir() {
+        sar_files(input).await?
+    } else {
+        vec![input.to_path_buf()]
+    };
+
+    let (mut renamed, mut duplicates) = (0, 0);
+    for file in files {
+        let bytes = fs::read(&file)
+            .await
+            .with_context(|| format!("failed to read: {}", file.to_string_lossy()))?;
+        let name = match fingerprint::cache_name(&bytes) {
+            Ok(name) => name,
+            Err(e) => {
+                eprintln!("failed to rename: {}: {}", file.to_string_lossy(), e);
+                continue;
+            }
+        };
+        let target = file.with_file_name(&name);
+        if target == file {
+            continue;
+        }
+        if target.exists() {
+            // The names are content digests, so the target holds the same bytes
+            println!("{} is a duplicate of {name}", file.to_string_lossy());
+            duplicates += 1;
+            continue;
+        }
+
+        println!("{} -> {name}", file.to_string_lossy());
+        if !args.dry_run {
+            fs::rename(&file, &target).await?;
+        }
+        renamed += 1;
+    }
+    println!("{renamed} files renamed, {duplicates} duplicates");
+
+    Ok(())
+}
+
+/// Lists the `.sar` files of a directory in name order
+pub async fn sar_files(dir: &Path) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let mut files = Vec::new();
+    let mut stream = ReadDirStream::new(fs::read_dir(dir).await?);
+    while let Some(entry) = stream.next().await {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "sar") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
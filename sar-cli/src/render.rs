@@ -0,0 +1,183 @@
+use anyhow::bail;
+use sar_core::renderer::draw::Drawer;
+use sar_core::{BlendSpace, LayerSelection, SymbolArtDrawer};
+use std::{io::Cursor, path::Path, sync::Arc};
+use tokio::{fs, task::spawn_blocking};
+use tokio_stream::{StreamExt, wrappers::ReadDirStream};
+
+use crate::args::parse_layer_range;
+
+#[derive(clap::Args, Debug)]
+pub struct RenderArgs {
+    /// Path to the SAR file or directory
+    #[arg(short, long)]
+    input: String,
+    /// Path to the output directory
+    #[arg(short, long)]
+    output: String,
+    /// Raise errors instead of ignoring them
+    #[arg(long, default_value_t = false)]
+    raise_error: bool,
+    /// Overwrite existing files
+    #[arg(long, default_value_t = false)]
+    overwrite: bool,
+    /// Blend layers in linear light instead of sRGB
+    #[arg(long, default_value_t = false)]
+    linear_blend: bool,
+    /// Only draw these layers, counted from the top: START..END, START.. or a list like 1,4,9
+    #[arg(long, value_parser = parse_layer_selection)]
+    layers: Option<LayerSelection>,
+    /// Draw the layers left out by --layers faded instead of not at all
+    #[arg(long, default_value_t = false, requires = "layers")]
+    ghost: bool,
+    /// Draw hidden layers as if they were visible
+    #[arg(long, default_value_t = false)]
+    show_hidden: bool,
+    /// Draw the whole canvas with the outline, corners and index of every layer
+    #[arg(long, default_value_t = false)]
+    overlay: bool,
+    /// Scale of the rendered images
+    #[arg(long, default_value_t = 1.0)]
+    scale: f32,
+}
+
+pub async fn run(args: RenderArgs) -> Result<(), anyhow::Error> {
+    let input = std::path::Path::new(&args.input);
+    let output = std::path::Path::new(&args.output);
+
+    if output.is_file() {
+        bail!("output_path already exists: {}", output.to_string_lossy())
+    }
+    if !output.parent().is_some_and(|parent| parent.exists()) {
+        bail!(
+            "parent path of the output_path doesn't exists: {}",
+            output.to_string_lossy()
+        )
+    }
+    if !output.exists() {
+        fs::create_dir(output).await?;
+    }
+
+    let drawer = Draw::new(
+        Arc::new(
+            sar_core::SymbolArtDrawer::new()
+                .with_raise_error(args.raise_error)
+                .with_blend_space(if args.linear_blend {
+                    BlendSpace::Linear
+                } else {
+                    BlendSpace::Srgb
+                })
+                .with_layers(args.layers.unwrap_or_default())
+                .with_ghost(args.ghost)
+                .with_show_hidden(args.show_hidden)
+                .with_overlay(args.overlay),
+        ),
+        args.scale,
+        args.overwrite,
+    );
+    if input.is_dir() {
+        drawer.draw_dir(input, output).await
+    } else {
+        let output = output.join(format!(
+            "{}.png",
+            input.file_name().unwrap().to_string_lossy()
+        ));
+        drawer.draw_file(input, &output).await
+    }
+}
+
+struct Draw {
+    drawer: Arc<SymbolArtDrawer>,
+    scale: f32,
+    overwrite: bool,
+}
+
+impl Draw {
+    fn new(drawer: Arc<SymbolArtDrawer>, scale: f32, overwrite: bool) -> Self {
+        Self {
+            drawer,
+            scale,
+            overwrite,
+        }
+    }
+}
+
+impl Draw {
+    async fn draw_dir(&self, input_dir: &Path, output_dir: &Path) -> Result<(), anyhow::Error> {
+        let mut stream = ReadDirStream::new(tokio::fs::read_dir(input_dir).await?);
+        while let Some(entry) = stream.next().await {
+            let entry = entry?;
+            let input_path = entry.path();
+            if input_path.is_dir() || input_path.is_symlink() {
+                continue;
+            }
+
+            let output_file = output_dir.join(format!(
+                "{}.png",
+                input_path.file_name().unwrap().to_string_lossy()
+            ));
+
+            let _ = self
+                .draw_file(&input_path, &output_file)
+                .await
+                .inspect_err(|e| {
+                    eprintln!("failed to render: {}: {}", input_path.to_string_lossy(), e)
+                });
+        }
+
+        Ok(())
+    }
+
+    async fn draw_file(&self, input_file: &Path, output_file: &Path) -> anyhow::Result<()> {
+        if !input_file.is_file() {
+            bail!("input_file not found: {}", input_file.to_string_lossy())
+        }
+        if input_file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext == ".sar")
+        {
+            bail!(
+                "input_file is not a sar file: {}",
+                input_file.to_string_lossy()
+            )
+        }
+        if output_file.exists() && !self.overwrite {
+            bail!(
+                "output_file already exists: {}",
+                output_file.to_string_lossy()
+            )
+        }
+
+        let bytes = tokio::fs::read(input_file).await?;
+        let parsed = sar_core::parse(bytes)?;
+
+        let drawer = self.drawer.clone();
+        let scale = self.scale;
+        let image = spawn_blocking(move || drawer.draw_with_scale(&parsed, scale)).await??;
+
+        let mut cursor = Cursor::new(Vec::new());
+        image.write_to(&mut cursor, image::ImageFormat::Png)?;
+
+        tokio::fs::write(output_file, cursor.into_inner()).await?;
+
+        Ok(())
+    }
+}
+
+/// Parses a layer selection as a range or a comma separated list of indices
+fn parse_layer_selection(value: &str) -> Result<LayerSelection, String> {
+    if value.contains("..") {
+        return parse_layer_range(value).map(LayerSelection::Range);
+    }
+    value
+        .split(',')
+        .map(|index| {
+            index
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| format!("invalid layer index: {e}"))
+        })
+        .collect::<Result<_, _>>()
+        .map(LayerSelection::Set)
+}
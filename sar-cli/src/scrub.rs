@@ -0,0 +1,84 @@
+use anyhow::{Context, bail};
+use sar_core::{Compression, author};
+use std::path::Path;
+use tokio::fs;
+use tokio_stream::{StreamExt, wrappers::ReadDirStream};
+
+#[derive(clap::Args, Debug)]
+pub struct ScrubArgs {
+    /// Path to the SAR file or directory
+    input: String,
+    /// Path to the output file, or directory when the input is a directory
+    #[arg(short, long, required_unless_present = "in_place")]
+    output: Option<String>,
+    /// Rewrite the input files instead of writing new ones
+    #[arg(long, default_value_t = false, conflicts_with = "output")]
+    in_place: bool,
+    /// Author ID to write instead of the original one
+    #[arg(long, default_value_t = author::ANONYMOUS)]
+    author_id: u32,
+    /// Overwrite existing files
+    #[arg(long, default_value_t = false)]
+    overwrite: bool,
+}
+
+pub async fn run(args: ScrubArgs) -> Result<(), anyhow::Error> {
+    let input = Path::new(&args.input);
+    let output = args.output.as_deref().map(Path::new);
+
+    if !input.is_dir() {
+        let output = output.unwrap_or(input);
+        return scrub_file(input, output, &args).await;
+    }
+
+    if let Some(output) = output {
+        if output.is_file() {
+            bail!("output_path already exists: {}", output.to_string_lossy())
+        }
+        if !output.exists() {
+            fs::create_dir(output).await?;
+        }
+    }
+
+    let mut scrubbed = 0;
+    let mut stream = ReadDirStream::new(fs::read_dir(input).await?);
+    while let Some(entry) = stream.next().await {
+        let input_path = entry?.path();
+        if !is_sar(&input_path) {
+            continue;
+        }
+        let output_path = match output {
+            Some(output) => output.join(input_path.file_name().unwrap()),
+            None => input_path.clone(),
+        };
+
+        match scrub_file(&input_path, &output_path, &args).await {
+            Ok(()) => scrubbed += 1,
+            Err(e) => eprintln!("failed to scrub: {}: {}", input_path.to_string_lossy(), e),
+        }
+    }
+    println!("{scrubbed} files scrubbed");
+
+    Ok(())
+}
+
+async fn scrub_file(input: &Path, output: &Path, args: &ScrubArgs) -> anyhow::Result<()> {
+    if output != input && output.exists() && !args.overwrite {
+        bail!("output_file already exists: {}", output.to_string_lossy())
+    }
+
+    let bytes = fs::read(input)
+        .await
+        .with_context(|| format!("failed to read: {}", input.to_string_lossy()))?;
+    let mut sa = sar_core::parse(bytes)?;
+    author::scrub(&mut sa, args.author_id);
+
+    let bytes = sar_core::encode(&sa, Compression::Compressed)?;
+    fs::write(output, bytes).await?;
+
+    Ok(())
+}
+
+fn is_sar(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext == "sar")
+}
@@ -0,0 +1,104 @@
+use anyhow::Context;
+use sar_core::{
+    SymbolArtDrawer,
+    analysis::{PerceptualHash, group_similar, perceptual_hash},
+};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::rename::sar_files;
+
+/// The largest distance between near-duplicates when clustering
+const DEFAULT_CLUSTER_DISTANCE: u32 = 10;
+
+#[derive(clap::Args, Debug)]
+pub struct SimilarArgs {
+    /// Path to the SAR file to search for
+    #[arg(required_unless_present = "cluster")]
+    query: Option<String>,
+    /// Path to the directory to search in
+    #[arg(required_unless_present = "cluster")]
+    dir: Option<String>,
+    /// Group the near-duplicates in a directory instead of searching
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["query", "dir"])]
+    cluster: Option<String>,
+    /// Only list arts at most this far apart, out of 64 [default when clustering: 10]
+    #[arg(long)]
+    max_distance: Option<u32>,
+    /// Maximum number of arts listed when searching
+    #[arg(long, default_value_t = 10)]
+    limit: usize,
+}
+
+pub async fn run(args: SimilarArgs) -> Result<(), anyhow::Error> {
+    let drawer = SymbolArtDrawer::new();
+
+    if let Some(dir) = &args.cluster {
+        let hashes = hash_dir(&drawer, Path::new(dir)).await?;
+        let max_distance = args.max_distance.unwrap_or(DEFAULT_CLUSTER_DISTANCE);
+        let groups = group_similar(
+            &hashes.iter().map(|(_, hash)| *hash).collect::<Vec<_>>(),
+            max_distance,
+        );
+
+        for (i, group) in groups.iter().enumerate() {
+            println!("group {}: {} arts", i + 1, group.len());
+            for member in group {
+                let (path, hash) = &hashes[*member];
+                println!("  {hash} {}", path.to_string_lossy());
+            }
+        }
+        println!(
+            "{} groups of near-duplicates among {} arts",
+            groups.len(),
+            hashes.len()
+        );
+        return Ok(());
+    }
+
+    let (Some(query), Some(dir)) = (&args.query, &args.dir) else {
+        unreachable!("clap requires the query and the directory without --cluster")
+    };
+    let bytes = fs::read(query)
+        .await
+        .with_context(|| format!("failed to read: {query}"))?;
+    let query_hash = perceptual_hash(&drawer, &sar_core::parse(bytes)?)?;
+    let query_path = fs::canonicalize(query).await?;
+
+    let mut ranked = Vec::new();
+    for (path, hash) in hash_dir(&drawer, Path::new(dir)).await? {
+        if fs::canonicalize(&path).await? == query_path {
+            continue;
+        }
+        let distance = query_hash.distance(&hash);
+        if args.max_distance.is_none_or(|max| distance <= max) {
+            ranked.push((distance, path));
+        }
+    }
+    ranked.sort();
+
+    for (distance, path) in ranked.iter().take(args.limit) {
+        println!("{distance:>2} {}", path.to_string_lossy());
+    }
+
+    Ok(())
+}
+
+/// Computes the perceptual hash of every SAR file in a directory, reusing one drawer
+async fn hash_dir(
+    drawer: &SymbolArtDrawer,
+    dir: &Path,
+) -> anyhow::Result<Vec<(PathBuf, PerceptualHash)>> {
+    let mut hashes = Vec::new();
+    for path in sar_files(dir).await? {
+        let bytes = fs::read(&path)
+            .await
+            .with_context(|| format!("failed to read: {}", path.to_string_lossy()))?;
+        let hash = sar_core::parse(bytes).and_then(|sa| perceptual_hash(drawer, &sa));
+        match hash {
+            Ok(hash) => hashes.push((path, hash)),
+            Err(e) => eprintln!("failed to hash: {}: {}", path.to_string_lossy(), e),
+        }
+    }
+    Ok(hashes)
+}
@@ -0,0 +1,83 @@
+use anyhow::{Context, bail};
+use sar_core::Compression;
+use std::path::Path;
+
+#[derive(clap::Args, Debug)]
+pub struct SplitArgs {
+    /// Path to the SAR file to split
+    input: String,
+    /// Path to the output directory
+    #[arg(short, long)]
+    output: String,
+    /// Grid to split into as COLUMNSxROWS, chosen automatically when omitted
+    #[arg(long, value_parser = parse_grid)]
+    grid: Option<(u32, u32)>,
+    /// Overwrite existing files
+    #[arg(long, default_value_t = false)]
+    overwrite: bool,
+}
+
+pub async fn run(args: SplitArgs) -> Result<(), anyhow::Error> {
+    let input = Path::new(&args.input);
+    let output = Path::new(&args.output);
+    if !output.is_dir() {
+        bail!("output directory not found: {}", output.to_string_lossy())
+    }
+
+    let bytes = tokio::fs::read(input)
+        .await
+        .with_context(|| format!("failed to read: {}", input.to_string_lossy()))?;
+    let sa = sar_core::parse(bytes)?;
+    let name = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "part".to_string());
+
+    let split = match args.grid {
+        Some((columns, rows)) => sar_core::layout::split(&sa, columns, rows, &name)?,
+        None => sar_core::layout::split_auto(&sa, &name)?,
+    };
+    if split.clipped_layers > 0 {
+        eprintln!(
+            "{} layer copies did not fit their part and were left out",
+            split.clipped_layers
+        );
+    }
+
+    let manifest = output.join(format!("{name}.layout"));
+    let files = split
+        .layout
+        .parts()
+        .iter()
+        .map(|part| output.join(&part.file))
+        .chain(std::iter::once(manifest.clone()))
+        .collect::<Vec<_>>();
+    if let Some(file) = files.iter().find(|file| file.exists())
+        && !args.overwrite
+    {
+        bail!("output_file already exists: {}", file.to_string_lossy())
+    }
+
+    for (part, file) in split.parts.iter().zip(&files) {
+        let bytes = sar_core::encode(part, Compression::Compressed)?;
+        tokio::fs::write(file, bytes).await?;
+    }
+    tokio::fs::write(&manifest, split.layout.to_string()).await?;
+
+    Ok(())
+}
+
+fn parse_grid(value: &str) -> Result<(u32, u32), String> {
+    let (columns, rows) = value
+        .split_once('x')
+        .ok_or_else(|| format!("expected COLUMNSxROWS but got: {value}"))?;
+    let columns = columns
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid columns: {e}"))?;
+    let rows = rows
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid rows: {e}"))?;
+    Ok((columns, rows))
+}
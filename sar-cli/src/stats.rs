@@ -0,0 +1,68 @@
+use anyhow::Context;
+use sar_core::{SymbolArtDrawer, stats::CorpusStats};
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+use tokio::fs;
+
+use crate::rename::sar_files;
+
+#[derive(clap::Args, Debug)]
+pub struct StatsArgs {
+    /// Path to the directory of SAR files
+    dir: String,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = StatsFormat::Json)]
+    format: StatsFormat,
+    /// Only parse the files, without counting render failures
+    #[arg(long)]
+    skip_render: bool,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum StatsFormat {
+    /// A JSON document
+    Json,
+    /// One `statistic,key,count` row per counted value
+    Csv,
+}
+
+pub async fn run(args: StatsArgs) -> Result<(), anyhow::Error> {
+    let files = sar_files(Path::new(&args.dir)).await?;
+    let drawer = (!args.skip_render).then(|| SymbolArtDrawer::new().with_raise_error(true));
+
+    let mut stats = CorpusStats::new();
+    for file in &files {
+        let bytes = fs::read(file)
+            .await
+            .with_context(|| format!("failed to read: {}", file.to_string_lossy()))?;
+        stats.add_file(bytes, drawer.as_ref());
+    }
+
+    match args.format {
+        StatsFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+        StatsFormat::Csv => print_csv(&stats),
+    }
+
+    Ok(())
+}
+
+fn print_csv(stats: &CorpusStats) {
+    println!("statistic,key,count");
+    println!("total,files,{}", stats.files);
+    println!("total,parsed,{}", stats.parsed());
+    rows("symbol", &stats.symbols);
+    rows("layers", &stats.layer_counts);
+    rows("canvas", &stats.canvases);
+    rows("color", &stats.colors);
+    for (level, count) in stats.alphas.iter().enumerate() {
+        println!("alpha,{level},{count}");
+    }
+    rows("sound-effect", &stats.sound_effects);
+    rows("parse-failure", &stats.parse_failures);
+    rows("render-failure", &stats.render_failures);
+}
+
+fn rows<K: Display>(statistic: &str, counts: &BTreeMap<K, usize>) {
+    for (key, count) in counts {
+        println!("{statistic},{key},{count}");
+    }
+}
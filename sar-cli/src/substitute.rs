@@ -0,0 +1,117 @@
+use anyhow::{Context, bail};
+use sar_core::{
+    Compression, SymbolId, edit::substitute, query::Predicate, renderer::resource::Resource,
+};
+use std::{collections::HashMap, ops::Range, path::Path};
+use tokio::fs;
+
+use crate::args::parse_layer_range;
+use crate::rename::sar_files;
+
+#[derive(clap::Args, Debug)]
+pub struct SubstituteArgs {
+    /// Path to the SAR file or directory
+    input: String,
+    /// Path to the output file, or directory when the input is a directory
+    #[arg(short, long, required_unless_present = "in_place")]
+    output: Option<String>,
+    /// Rewrite the input files instead of writing new ones
+    #[arg(long, default_value_t = false, conflicts_with = "output")]
+    in_place: bool,
+    /// Replace a symbol with another as FROM=TO, e.g. 241=242
+    #[arg(long, required = true, value_parser = parse_mapping)]
+    map: Vec<(SymbolId, SymbolId)>,
+    /// Only substitute in the layers in the range, e.g. 10..40
+    #[arg(long, value_parser = parse_layer_range)]
+    layers: Option<Range<usize>>,
+    /// Overwrite existing files
+    #[arg(long, default_value_t = false)]
+    overwrite: bool,
+}
+
+pub async fn run(args: SubstituteArgs) -> Result<(), anyhow::Error> {
+    let input = Path::new(&args.input);
+    let output = args.output.as_deref().map(Path::new);
+    let map = args.map.iter().copied().collect::<HashMap<_, _>>();
+    if let Some(target) = map.values().find(|target| !Resource::contains(**target)) {
+        bail!("symbol {target} cannot be drawn")
+    }
+
+    if !input.is_dir() {
+        let output = output.unwrap_or(input);
+        let changed = substitute_file(input, output, &map, &args).await?;
+        println!("{}: {changed} layers changed", input.to_string_lossy());
+        return Ok(());
+    }
+
+    if let Some(output) = output {
+        if output.is_file() {
+            bail!("output_path already exists: {}", output.to_string_lossy())
+        }
+        if !output.exists() {
+            fs::create_dir(output).await?;
+        }
+    }
+
+    let mut total = 0;
+    for input_path in sar_files(input).await? {
+        let output_path = match output {
+            Some(output) => output.join(input_path.file_name().unwrap()),
+            None => input_path.clone(),
+        };
+
+        match substitute_file(&input_path, &output_path, &map, &args).await {
+            Ok(changed) => {
+                println!("{}: {changed} layers changed", input_path.to_string_lossy());
+                total += changed;
+            }
+            Err(e) => eprintln!(
+                "failed to substitute: {}: {}",
+                input_path.to_string_lossy(),
+                e
+            ),
+        }
+    }
+    println!("{total} layers changed in total");
+
+    Ok(())
+}
+
+async fn substitute_file(
+    input: &Path,
+    output: &Path,
+    map: &HashMap<SymbolId, SymbolId>,
+    args: &SubstituteArgs,
+) -> anyhow::Result<usize> {
+    if output != input && output.exists() && !args.overwrite {
+        bail!("output_file already exists: {}", output.to_string_lossy())
+    }
+
+    let bytes = fs::read(input)
+        .await
+        .with_context(|| format!("failed to read: {}", input.to_string_lossy()))?;
+    let mut sa = sar_core::parse(bytes)?;
+    let selection = args.layers.clone().map(Predicate::index_range);
+    let changed = substitute(&mut sa, map, selection.as_ref())?;
+
+    // Files without any change are left untouched when rewritten in place
+    if changed > 0 || output != input {
+        let bytes = sar_core::encode(&sa, Compression::Compressed)?;
+        fs::write(output, bytes).await?;
+    }
+
+    Ok(changed)
+}
+
+fn parse_mapping(value: &str) -> Result<(SymbolId, SymbolId), String> {
+    let (from, to) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected FROM=TO but got: {value}"))?;
+    let parse = |id: &str| {
+        id.trim()
+            .parse()
+            .map(SymbolId::new)
+            .map_err(|e| format!("invalid symbol id: {e}"))
+    };
+    Ok((parse(from)?, parse(to)?))
+}
@@ -0,0 +1,50 @@
+use anyhow::{Context, bail};
+use sar_core::fingerprint;
+use std::path::Path;
+use tokio::fs;
+
+use crate::rename::sar_files;
+
+#[derive(clap::Args, Debug)]
+pub struct VerifyArgs {
+    /// Path to the SAR file or directory
+    input: String,
+}
+
+pub async fn run(args: VerifyArgs) -> Result<(), anyhow::Error> {
+    let input = Path::new(&args.input);
+    let files = if input.is_dir() {
+        sar_files(input).await?
+    } else {
+        vec![input.to_path_buf()]
+    };
+
+    let mut mismatches = 0;
+    for file in &files {
+        let bytes = fs::read(file)
+            .await
+            .with_context(|| format!("failed to read: {}", file.to_string_lossy()))?;
+        let file_name = file.file_name().unwrap().to_string_lossy();
+        match fingerprint::cache_name(&bytes) {
+            Ok(name) if name == file_name => {}
+            Ok(name) => {
+                println!("{}: expected {name}", file.to_string_lossy());
+                mismatches += 1;
+            }
+            Err(e) => {
+                println!("{}: {e}", file.to_string_lossy());
+                mismatches += 1;
+            }
+        }
+    }
+
+    if mismatches > 0 {
+        bail!(
+            "{mismatches} of {} files do not match their name",
+            files.len()
+        )
+    }
+    println!("{} files match their name", files.len());
+
+    Ok(())
+}
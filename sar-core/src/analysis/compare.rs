@@ -0,0 +1,196 @@
+use image::{Pixel, Rgba, RgbaImage, imageops};
+
+use crate::core::{
+    result::{Result, SARError},
+    sa::{SymbolArt, SymbolArtLayer},
+};
+use crate::renderer::draw::{Drawer, SymbolArtDrawer};
+
+/// The color marking changed pixels in [`CompareMode::Highlight`]
+const HIGHLIGHT: Rgba<u8> = Rgba([255, 0, 64, 255]);
+
+/// How the comparison image shows the two renders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompareMode {
+    /// The second render faded to gray, with changed pixels in a solid color
+    #[default]
+    Highlight,
+    /// The two renders next to each other, the first on the left
+    SideBySide,
+    /// The second render at half opacity over the first
+    OnionSkin,
+}
+
+/// The outcome of comparing two renders
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// The number of pixels that differ in any channel
+    pub changed_pixels: usize,
+    /// The number of pixels compared
+    pub total_pixels: usize,
+    /// The largest difference of a single channel, alpha included
+    pub max_delta: u8,
+    /// The comparison image in the requested mode
+    pub image: RgbaImage,
+}
+
+impl Comparison {
+    /// Returns the share of changed pixels, between 0 and 1
+    pub fn changed_ratio(&self) -> f32 {
+        if self.total_pixels == 0 {
+            return 0.0;
+        }
+        self.changed_pixels as f32 / self.total_pixels as f32
+    }
+}
+
+/// Renders two SymbolArts at the same scale and compares them pixel by pixel
+///
+/// Renders of different canvas sizes are centered on the larger size, with
+/// transparent pixels around the smaller one. Two fully transparent pixels are
+/// equal whatever their color channels hold.
+///
+/// Returns [`SARError::UnsupportedCanvas`] when either art has an unknown canvas.
+pub fn compare<A, B, L, M>(
+    drawer: &SymbolArtDrawer,
+    before: &A,
+    after: &B,
+    scale: f32,
+    mode: CompareMode,
+) -> Result<Comparison>
+where
+    A: SymbolArt<Layer = L>,
+    B: SymbolArt<Layer = M>,
+    L: SymbolArtLayer + Sync,
+    M: SymbolArtLayer + Sync,
+{
+    if before.canvas().is_none() || after.canvas().is_none() {
+        return Err(SARError::UnsupportedCanvas(
+            "unknown canvas size".to_string(),
+        ));
+    }
+
+    let before = drawer.draw_with_scale(before, scale)?;
+    let after = drawer.draw_with_scale(after, scale)?;
+    let (width, height) = (
+        before.width().max(after.width()),
+        before.height().max(after.height()),
+    );
+    let (before, after) = (
+        center(&before, width, height),
+        center(&after, width, height),
+    );
+
+    let mut changed = vec![false; (width * height) as usize];
+    let mut max_delta = 0;
+    for ((a, b), changed) in before.pixels().zip(after.pixels()).zip(&mut changed) {
+        if a[3] == 0 && b[3] == 0 {
+            continue;
+        }
+        let delta = (0..4).map(|c| a[c].abs_diff(b[c])).max().unwrap_or(0);
+        *changed = delta > 0;
+        max_delta = max_delta.max(delta);
+    }
+
+    let image = match mode {
+        CompareMode::Highlight => RgbaImage::from_fn(width, height, |x, y| {
+            if changed[(y * width + x) as usize] {
+                return HIGHLIGHT;
+            }
+            let pixel = after.get_pixel(x, y);
+            let gray = pixel.to_luma()[0];
+            Rgba([gray, gray, gray, pixel[3] / 3])
+        }),
+        CompareMode::SideBySide => {
+            let mut image = RgbaImage::new(width * 2, height);
+            imageops::replace(&mut image, &before, 0, 0);
+            imageops::replace(&mut image, &after, width as i64, 0);
+            image
+        }
+        CompareMode::OnionSkin => {
+            let mut image = before.clone();
+            for (pixel, over) in image.pixels_mut().zip(after.pixels()) {
+                pixel.blend(&Rgba([over[0], over[1], over[2], over[3] / 2]));
+            }
+            image
+        }
+    };
+
+    Ok(Comparison {
+        changed_pixels: changed.iter().filter(|changed| **changed).count(),
+        total_pixels: changed.len(),
+        max_delta,
+        image,
+    })
+}
+
+/// Places an image at the center of a transparent one of the given size
+fn center(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    if image.dimensions() == (width, height) {
+        return image.clone();
+    }
+    let mut canvas = RgbaImage::new(width, height);
+    imageops::replace(
+        &mut canvas,
+        image,
+        ((width - image.width()) / 2) as i64,
+        ((height - image.height()) / 2) as i64,
+    );
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Payload, parse, test::RAW_FILE};
+
+    #[test]
+    fn test_compare_identical() {
+        let drawer = SymbolArtDrawer::new();
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let comparison = compare(&drawer, &sa, &sa, 1.0, CompareMode::Highlight).unwrap();
+
+        assert_eq!(comparison.changed_pixels, 0);
+        assert_eq!(comparison.max_delta, 0);
+        assert_eq!(comparison.total_pixels, 193 * 96);
+        assert!(comparison.image.pixels().all(|pixel| *pixel != HIGHLIGHT));
+    }
+
+    #[test]
+    fn test_compare_unknown_canvas() {
+        let drawer = SymbolArtDrawer::new();
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        // The height byte of the header, set to neither known canvas
+        let mut bytes = sa.to_bytes();
+        bytes[5] = 0x20;
+        let unknown = Payload::parse(&bytes).unwrap();
+
+        let comparison = compare(&drawer, &sa, &unknown, 1.0, CompareMode::Highlight);
+        assert!(matches!(comparison, Err(SARError::UnsupportedCanvas(_))));
+    }
+
+    #[test]
+    fn test_compare_modes() {
+        let drawer = SymbolArtDrawer::new();
+        let before = parse(Vec::from(RAW_FILE)).unwrap();
+        let mut after = before.clone();
+        after.layers_mut().remove(0);
+
+        let comparison = compare(&drawer, &before, &after, 1.0, CompareMode::Highlight).unwrap();
+        assert!(comparison.changed_pixels > 0);
+        assert!(comparison.changed_ratio() > 0.0 && comparison.changed_ratio() < 1.0);
+        assert_eq!(
+            comparison
+                .image
+                .pixels()
+                .filter(|pixel| **pixel == HIGHLIGHT)
+                .count(),
+            comparison.changed_pixels
+        );
+
+        let side_by_side = compare(&drawer, &before, &after, 2.0, CompareMode::SideBySide).unwrap();
+        assert_eq!(side_by_side.image.dimensions(), (193 * 4, 96 * 2));
+        let onion_skin = compare(&drawer, &before, &after, 1.0, CompareMode::OnionSkin).unwrap();
+        assert_eq!(onion_skin.image.dimensions(), (193, 96));
+    }
+}
@@ -0,0 +1,18 @@
+//! Read-only analyses of SymbolArts
+//!
+//! Analyses never modify the SymbolArt. Those that need rendered pixels take a
+//! [`SymbolArtDrawer`](crate::SymbolArtDrawer) so callers can reuse one across
+//! many files.
+
+mod compare;
+mod palette;
+mod similarity;
+mod visibility;
+
+pub use compare::{CompareMode, Comparison, compare};
+pub(crate) use palette::cluster;
+pub use palette::{
+    LayerColor, Palette, PixelColor, dominant_colors, layer_colors, palette, pixel_colors,
+};
+pub use similarity::{PerceptualHash, group_similar, image_hash, perceptual_hash};
+pub use visibility::{LayerVisibility, Visibility, visibility};
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use image::RgbaImage;
+
+use crate::Result;
+use crate::core::{
+    color::ColorSpace,
+    sa::{Color, SymbolArt, SymbolArtLayer},
+};
+use crate::renderer::draw::{Drawer, SymbolArtDrawer};
+
+/// The number of refinement passes when clustering colors
+const CLUSTER_ITERATIONS: usize = 16;
+
+/// A color and how many visible layers use it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerColor {
+    pub color: Color,
+    pub layers: usize,
+}
+
+/// A color and how much of the rendered image it covers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelColor {
+    pub color: Color,
+    pub pixels: usize,
+    /// The fraction of the non-transparent pixels covered, between 0 and 1
+    pub share: f32,
+}
+
+/// The colors used by a SymbolArt
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    /// Distinct colors of the visible layers, most used first
+    pub layer_colors: Vec<LayerColor>,
+    /// Distinct colors of the rendered pixels, most used first
+    pub pixel_colors: Vec<PixelColor>,
+    /// Representative colors of the rendered pixels after clustering, most used first
+    pub dominant_colors: Vec<PixelColor>,
+}
+
+/// Analyzes the colors of a SymbolArt
+///
+/// The SymbolArt is rendered with `drawer`, and the rendered pixels are grouped
+/// into at most `dominant` clusters in CIELAB.
+pub fn palette<S, L>(drawer: &SymbolArtDrawer, sa: &S, dominant: usize) -> Result<Palette>
+where
+    S: SymbolArt<Layer = L>,
+    L: SymbolArtLayer + Sync,
+{
+    let image = drawer.draw(sa)?;
+    let pixel_colors = pixel_colors(&image);
+    let dominant_colors = dominant_colors(&pixel_colors, dominant);
+
+    Ok(Palette {
+        layer_colors: layer_colors(sa),
+        pixel_colors,
+        dominant_colors,
+    })
+}
+
+/// Counts the distinct colors of the visible layers, most used first
+///
+/// Colors are compared including alpha.
+pub fn layer_colors<S>(sa: &S) -> Vec<LayerColor>
+where
+    S: SymbolArt,
+{
+    let mut counts: HashMap<Color, usize> = HashMap::new();
+    for layer in sa.layers().iter().filter(|layer| !layer.is_hidden()) {
+        *counts.entry(layer.color()).or_default() += 1;
+    }
+
+    let mut colors = counts
+        .into_iter()
+        .map(|(color, layers)| LayerColor { color, layers })
+        .collect::<Vec<_>>();
+    colors.sort_by_key(|c| (std::cmp::Reverse(c.layers), c.color.to_hex(), c.color.a));
+    colors
+}
+
+/// Counts the distinct colors of the non-transparent pixels, most used first
+///
+/// Pixels are compared ignoring alpha, and the resulting colors are opaque.
+pub fn pixel_colors(image: &RgbaImage) -> Vec<PixelColor> {
+    let mut counts: HashMap<[u8; 3], usize> = HashMap::new();
+    let mut total = 0;
+    for pixel in image.pixels().filter(|pixel| pixel[3] > 0) {
+        *counts.entry([pixel[0], pixel[1], pixel[2]]).or_default() += 1;
+        total += 1;
+    }
+
+    let mut colors = counts
+        .into_iter()
+        .map(|([r, g, b], pixels)| PixelColor {
+            color: Color::new(255, r, g, b),
+            pixels,
+            share: pixels as f32 / total as f32,
+        })
+        .collect::<Vec<_>>();
+    colors.sort_by_key(|c| (std::cmp::Reverse(c.pixels), c.color.to_hex()));
+    colors
+}
+
+/// Groups colors into at most `k` clusters, most used first
+///
+/// Each cluster is represented by the pixel-weighted mean of its members.
+pub fn dominant_colors(colors: &[PixelColor], k: usize) -> Vec<PixelColor> {
+    let weighted = colors
+        .iter()
+        .map(|c| (c.color, c.pixels as f32))
+        .collect::<Vec<_>>();
+    let total = colors.iter().map(|c| c.pixels).sum::<usize>();
+
+    let mut dominant = cluster(&weighted, k, ColorSpace::Lab)
+        .into_iter()
+        .map(|cluster| {
+            let pixels = cluster.members.iter().map(|i| colors[*i].pixels).sum();
+            PixelColor {
+                color: cluster.color,
+                pixels,
+                share: pixels as f32 / total as f32,
+            }
+        })
+        .collect::<Vec<_>>();
+    dominant.sort_by_key(|c| (std::cmp::Reverse(c.pixels), c.color.to_hex()));
+    dominant
+}
+
+/// A group of similar colors
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cluster {
+    /// The weighted mean of the members
+    pub(crate) color: Color,
+    /// The indices of the member colors
+    pub(crate) members: Vec<usize>,
+}
+
+/// Groups weighted colors into at most `k` clusters with k-means
+///
+/// The initial centers are picked deterministically: the heaviest color first,
+/// then repeatedly the color furthest from every center picked so far. Alpha is
+/// ignored and the cluster colors are opaque.
+pub(crate) fn cluster(colors: &[(Color, f32)], k: usize, space: ColorSpace) -> Vec<Cluster> {
+    if colors.is_empty() || k == 0 {
+        return Vec::new();
+    }
+
+    let mut centers: Vec<Color> = Vec::new();
+    let heaviest = colors
+        .iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(color, _)| *color)
+        .unwrap();
+    centers.push(heaviest);
+    while centers.len() < k {
+        let furthest = colors
+            .iter()
+            .map(|(color, _)| {
+                let distance = centers
+                    .iter()
+                    .map(|center| color.distance_in(center, space))
+                    .fold(f32::INFINITY, f32::min);
+                (color, distance)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match furthest {
+            Some((color, distance)) if distance > 0.0 => centers.push(*color),
+            _ => break,
+        }
+    }
+
+    let mut assignments = vec![0; colors.len()];
+    for _ in 0..CLUSTER_ITERATIONS {
+        for (i, (color, _)) in colors.iter().enumerate() {
+            assignments[i] = nearest(color, &centers, space);
+        }
+
+        let mut changed = false;
+        for (c, center) in centers.iter_mut().enumerate() {
+            let members = colors
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, assignment)| **assignment == c)
+                .map(|(member, _)| *member)
+                .collect::<Vec<_>>();
+            if members.is_empty() {
+                continue;
+            }
+            let mean = weighted_mean(&members);
+            if mean != *center {
+                *center = mean;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    for (i, (color, _)) in colors.iter().enumerate() {
+        assignments[i] = nearest(color, &centers, space);
+    }
+    centers
+        .into_iter()
+        .enumerate()
+        .map(|(c, color)| Cluster {
+            color,
+            members: (0..colors.len()).filter(|i| assignments[*i] == c).collect(),
+        })
+        .filter(|cluster| !cluster.members.is_empty())
+        .collect()
+}
+
+fn nearest(color: &Color, centers: &[Color], space: ColorSpace) -> usize {
+    centers
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            color
+                .distance_in(a, space)
+                .total_cmp(&color.distance_in(b, space))
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+fn weighted_mean(colors: &[(Color, f32)]) -> Color {
+    let total = colors
+        .iter()
+        .map(|(_, weight)| weight)
+        .sum::<f32>()
+        .max(f32::EPSILON);
+    let channel = |f: fn(&Color) -> u8| {
+        let sum = colors
+            .iter()
+            .map(|(color, weight)| f(color) as f32 * weight)
+            .sum::<f32>();
+        (sum / total).round().clamp(0.0, 255.0) as u8
+    };
+    Color::new(255, channel(|c| c.r), channel(|c| c.g), channel(|c| c.b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, test::RAW_FILE};
+
+    #[test]
+    fn test_palette() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let palette = palette(&SymbolArtDrawer::default(), &sa, 4).unwrap();
+
+        let visible = sa.layers().iter().filter(|l| !l.is_hidden()).count();
+        let counted = palette.layer_colors.iter().map(|c| c.layers).sum::<usize>();
+        assert_eq!(counted, visible);
+        assert!(
+            palette
+                .layer_colors
+                .windows(2)
+                .all(|w| w[0].layers >= w[1].layers)
+        );
+
+        let share = palette.pixel_colors.iter().map(|c| c.share).sum::<f32>();
+        assert!((share - 1.0).abs() < 1e-3);
+
+        assert!(!palette.dominant_colors.is_empty());
+        assert!(palette.dominant_colors.len() <= 4);
+        let pixels = palette.pixel_colors.iter().map(|c| c.pixels).sum::<usize>();
+        let dominant = palette
+            .dominant_colors
+            .iter()
+            .map(|c| c.pixels)
+            .sum::<usize>();
+        assert_eq!(pixels, dominant);
+    }
+
+    #[test]
+    fn test_cluster_separates_distinct_colors() {
+        let red = Color::new(255, 255, 0, 0);
+        let dark_red = Color::new(255, 250, 4, 0);
+        let blue = Color::new(255, 0, 0, 255);
+        let clusters = cluster(
+            &[(red, 10.0), (dark_red, 1.0), (blue, 5.0)],
+            2,
+            ColorSpace::Lab,
+        );
+
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].members, vec![0, 1]);
+        assert_eq!(clusters[1].members, vec![2]);
+        assert_eq!(clusters[1].color, blue);
+    }
+}
@@ -0,0 +1,196 @@
+use std::{f32::consts::PI, fmt, str::FromStr};
+
+use image::{GrayImage, Luma, Pixel, RgbaImage, imageops};
+
+use crate::Result;
+use crate::core::{
+    result::SARError,
+    sa::{SymbolArt, SymbolArtLayer},
+};
+use crate::renderer::draw::{Drawer, SymbolArtDrawer};
+
+/// The side of the grayscale thumbnail the hash is computed from
+const SAMPLE_SIZE: usize = 32;
+/// The side of the block of lowest frequencies kept in the hash
+const FREQUENCIES: usize = 8;
+
+/// A 64-bit perceptual hash, formatted as 16 lowercase hex digits
+///
+/// Arts that look alike have hashes a small [`distance`](Self::distance) apart,
+/// even when their layers differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PerceptualHash(u64);
+
+impl PerceptualHash {
+    /// Returns the number of bits that differ from `other`, between 0 and 64
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for PerceptualHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for PerceptualHash {
+    type Err = SARError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.len() != 16 {
+            return Err(SARError::InvalidDigest(s.to_string()));
+        }
+        u64::from_str_radix(s, 16)
+            .map(Self)
+            .map_err(|_| SARError::InvalidDigest(s.to_string()))
+    }
+}
+
+/// Computes the perceptual hash of a SymbolArt from its render by `drawer`
+pub fn perceptual_hash<S, L>(drawer: &SymbolArtDrawer, sa: &S) -> Result<PerceptualHash>
+where
+    S: SymbolArt<Layer = L>,
+    L: SymbolArtLayer + Sync,
+{
+    Ok(image_hash(&drawer.draw(sa)?))
+}
+
+/// Computes the perceptual hash of a rendered image
+///
+/// The image is flattened onto white, reduced to a 32x32 grayscale thumbnail
+/// and transformed with a DCT. Each bit tells whether one of the 8x8 lowest
+/// frequencies is above their median, so the hash follows the overall shapes
+/// and shading rather than exact pixels.
+pub fn image_hash(image: &RgbaImage) -> PerceptualHash {
+    let gray = GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let pixel = image.get_pixel(x, y);
+        let alpha = pixel[3] as f32 / 255.0;
+        let luma = pixel.to_luma()[0] as f32;
+        Luma([(luma * alpha + 255.0 * (1.0 - alpha)).round() as u8])
+    });
+    let thumbnail = imageops::resize(
+        &gray,
+        SAMPLE_SIZE as u32,
+        SAMPLE_SIZE as u32,
+        imageops::FilterType::Triangle,
+    );
+
+    let cosines = (0..FREQUENCIES)
+        .map(|u| {
+            (0..SAMPLE_SIZE)
+                .map(|x| ((2 * x + 1) as f32 * u as f32 * PI / (2 * SAMPLE_SIZE) as f32).cos())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // The transform is separable: rows first, then the columns of the result
+    let mut rows = vec![[0.0f32; FREQUENCIES]; SAMPLE_SIZE];
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (u, value) in row.iter_mut().enumerate() {
+            *value = (0..SAMPLE_SIZE)
+                .map(|x| thumbnail.get_pixel(x as u32, y as u32)[0] as f32 * cosines[u][x])
+                .sum();
+        }
+    }
+    let mut coefficients = Vec::with_capacity(FREQUENCIES * FREQUENCIES);
+    for cosine in &cosines {
+        coefficients.extend((0..FREQUENCIES).map(|u| {
+            rows.iter()
+                .zip(cosine)
+                .map(|(row, cosine)| row[u] * cosine)
+                .sum::<f32>()
+        }));
+    }
+
+    // The first coefficient is the mean brightness and would skew the median
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let median = sorted[sorted.len() / 2];
+
+    let bits = coefficients
+        .iter()
+        .enumerate()
+        .filter(|(_, coefficient)| **coefficient > median)
+        .fold(0u64, |bits, (i, _)| bits | 1 << i);
+    PerceptualHash(bits)
+}
+
+/// Groups hashes that are at most `max_distance` apart, directly or through others
+///
+/// Returns the indices of every group with more than one member, each in
+/// ascending order, ordered by their first index.
+pub fn group_similar(hashes: &[PerceptualHash], max_distance: u32) -> Vec<Vec<usize>> {
+    let mut parents = (0..hashes.len()).collect::<Vec<_>>();
+    for i in 0..hashes.len() {
+        for j in i + 1..hashes.len() {
+            if hashes[i].distance(&hashes[j]) <= max_distance {
+                let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of: Vec<Option<usize>> = vec![None; hashes.len()];
+    for i in 0..hashes.len() {
+        let root = root(&mut parents, i);
+        match group_of[root] {
+            Some(group) => groups[group].push(i),
+            None => {
+                group_of[root] = Some(groups.len());
+                groups.push(vec![i]);
+            }
+        }
+    }
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+/// Finds the representative of the group of `i`, flattening the path to it
+fn root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse,
+        test::{RAW_FILE, RAW_FILE_UNCOMPRESSED},
+    };
+
+    #[test]
+    fn test_perceptual_hash() {
+        let drawer = SymbolArtDrawer::new();
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let mut edited = sa.clone();
+        edited.layers_mut().remove(0);
+        let other = parse(Vec::from(RAW_FILE_UNCOMPRESSED)).unwrap();
+
+        let hash = perceptual_hash(&drawer, &sa).unwrap();
+        assert_eq!(hash, perceptual_hash(&drawer, &sa.clone()).unwrap());
+        let edited = perceptual_hash(&drawer, &edited).unwrap();
+        let other = perceptual_hash(&drawer, &other).unwrap();
+        assert!(hash.distance(&edited) < hash.distance(&other));
+        assert!(hash.distance(&other) > 16);
+
+        assert_eq!(hash.to_string().parse::<PerceptualHash>().unwrap(), hash);
+        assert!("xyz".parse::<PerceptualHash>().is_err());
+    }
+
+    #[test]
+    fn test_group_similar() {
+        let hashes = [0b0000, 0b0001, 0xff00, 0b0011, 0xff01].map(PerceptualHash);
+        assert_eq!(group_similar(&hashes, 1), vec![vec![0, 1, 3], vec![2, 4]]);
+        assert!(group_similar(&hashes, 0).is_empty());
+    }
+}
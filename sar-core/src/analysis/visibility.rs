@@ -0,0 +1,172 @@
+use image::{ImageBuffer, Luma, Rgba, RgbaImage};
+
+use crate::Result;
+use crate::core::sa::{SymbolArt, SymbolArtLayer};
+use crate::renderer::draw::SymbolArtDrawer;
+
+/// How much of the view a drawn layer paints and how much of it shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayerVisibility {
+    /// The view pixels the layer paints with a non-zero alpha
+    pub covered: usize,
+    /// The covered pixels not under a fully opaque layer above it
+    pub visible: usize,
+}
+
+/// The per-layer visibility and overdraw of a rendered SymbolArt
+#[derive(Debug, Clone, PartialEq)]
+pub struct Visibility {
+    /// The visibility of every layer counted from the top, or `None` for
+    /// hidden layers and layers that cannot be rendered
+    pub layers: Vec<Option<LayerVisibility>>,
+    /// The number of layers painting each view pixel
+    pub overdraw: ImageBuffer<Luma<u16>, Vec<u16>>,
+}
+
+impl Visibility {
+    /// Returns the largest number of layers stacked on a single pixel
+    pub fn max_overdraw(&self) -> u16 {
+        self.overdraw
+            .pixels()
+            .map(|count| count[0])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Renders the overdraw as a heatmap
+    ///
+    /// Pixels without any layer stay transparent. The others go from blue for a
+    /// single layer through green and yellow to red for the most stacked pixel.
+    pub fn heatmap(&self) -> RgbaImage {
+        let max = self.max_overdraw();
+        RgbaImage::from_fn(self.overdraw.width(), self.overdraw.height(), |x, y| {
+            let count = self.overdraw.get_pixel(x, y)[0];
+            if count == 0 {
+                return Rgba([0; 4]);
+            }
+            let t = if max > 1 {
+                (count - 1) as f32 / (max - 1) as f32
+            } else {
+                0.0
+            };
+            heat(t)
+        })
+    }
+}
+
+/// Attributes every view pixel to the layers painting it
+///
+/// A covered pixel stays visible unless a layer above paints it fully opaque,
+/// so a layer with no visible pixel does not change the rendered image. The
+/// layers are rasterized with `drawer` exactly as they are composited.
+pub fn visibility<S, L>(drawer: &SymbolArtDrawer, sa: &S) -> Result<Visibility>
+where
+    S: SymbolArt<Layer = L>,
+    L: SymbolArtLayer + Sync,
+{
+    let masks = drawer.layer_masks(sa)?;
+    let mut overdraw: ImageBuffer<Luma<u16>, _> =
+        ImageBuffer::new(sa.width() as u32, sa.height() as u32);
+
+    // Pixels already painted fully opaque by the layers above
+    let mut opaque = vec![false; overdraw.len()];
+    let layers = masks
+        .iter()
+        .map(|mask| {
+            let mask = mask.as_ref()?;
+            let mut layer = LayerVisibility::default();
+            for ((alpha, opaque), count) in mask
+                .pixels()
+                .zip(opaque.iter_mut())
+                .zip(overdraw.pixels_mut())
+            {
+                if alpha[0] == 0 {
+                    continue;
+                }
+                layer.covered += 1;
+                count[0] += 1;
+                if !*opaque {
+                    layer.visible += 1;
+                }
+                *opaque |= alpha[0] == u8::MAX;
+            }
+            Some(layer)
+        })
+        .collect();
+
+    Ok(Visibility { layers, overdraw })
+}
+
+/// Maps a value between 0 and 1 onto a blue, green, yellow and red ramp
+fn heat(t: f32) -> Rgba<u8> {
+    const STOPS: [[f32; 3]; 4] = [
+        [0.0, 0.0, 255.0],
+        [0.0, 255.0, 0.0],
+        [255.0, 255.0, 0.0],
+        [255.0, 0.0, 0.0],
+    ];
+    let position = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let i = (position as usize).min(STOPS.len() - 2);
+    let f = position - i as f32;
+    let channel = |c: usize| (STOPS[i][c] + (STOPS[i + 1][c] - STOPS[i][c]) * f).round() as u8;
+    Rgba([channel(0), channel(1), channel(2), 255])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Layer, SymbolId, core::sa::Position, parse, test::RAW_FILE};
+
+    #[test]
+    fn test_visibility() {
+        let drawer = SymbolArtDrawer::new();
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let visibility = visibility(&drawer, &sa).unwrap();
+
+        assert_eq!(visibility.layers.len(), sa.layers().len());
+        assert_eq!(
+            visibility.overdraw.dimensions(),
+            (sa.width() as u32, sa.height() as u32)
+        );
+        let covered: usize = visibility.layers.iter().flatten().map(|l| l.covered).sum();
+        let stacked: usize = visibility.overdraw.pixels().map(|c| c[0] as usize).sum();
+        assert_eq!(covered, stacked);
+        for layer in visibility.layers.iter().flatten() {
+            assert!(layer.visible <= layer.covered);
+        }
+    }
+
+    #[test]
+    fn test_visibility_occlusion() {
+        let drawer = SymbolArtDrawer::new();
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let p = |x, y| Position { x, y };
+
+        // A small opaque disc inside a large one, then a hidden layer
+        let mut cover = Layer::new(SymbolId::new(731));
+        cover.set_vertices([p(96, 96), p(96, 160), p(160, 96), p(160, 160)]);
+        let mut covered = cover;
+        covered.set_vertices([p(120, 120), p(120, 136), p(136, 120), p(136, 136)]);
+        let mut hidden = cover;
+        hidden.set_hidden(true);
+        let layers = sa.layers_mut();
+        layers.clear();
+        layers.extend([cover, covered, hidden]);
+
+        let visibility = visibility(&drawer, &sa).unwrap();
+        let top = visibility.layers[0].unwrap();
+        let below = visibility.layers[1].unwrap();
+        assert_eq!(top.visible, top.covered);
+        assert!(below.covered > 0);
+        assert_eq!(below.visible, 0);
+        assert_eq!(visibility.layers[2], None);
+        assert_eq!(visibility.max_overdraw(), 2);
+
+        let heatmap = visibility.heatmap();
+        assert_eq!(heatmap.get_pixel(0, 0), &Rgba([0; 4]));
+        assert_eq!(
+            heatmap.get_pixel(128 - 32, 128 - 80),
+            &Rgba([255, 0, 0, 255])
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Author ID privacy and display names
+//!
+//! Every SAR file carries the account ID of its author. [`scrub`] replaces it
+//! before an art is shared, and a [`Registry`] maps the IDs of known players to
+//! display names for local listings.
+//!
+//! The registry is stored as a text file with one `<author id> <name>` pair per
+//! line. Blank lines and lines starting with `#` are ignored.
+//!
+//! ```
+//! use sar_core::author::Registry;
+//!
+//! let registry: Registry = "# teammates\n881302016 Aki\n".parse().unwrap();
+//! assert_eq!(registry.name(881302016), Some("Aki"));
+//! assert_eq!(registry.display(42), "42");
+//! ```
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use crate::core::{result::SARError, sa::SymbolArt};
+use crate::parser::payload::Payload;
+
+/// The author ID written by [`scrub`] when no replacement is given
+pub const ANONYMOUS: u32 = 0;
+
+/// Replaces the author ID of a SymbolArt
+///
+/// Returns the previous author ID.
+pub fn scrub(sa: &mut Payload, author_id: u32) -> u32 {
+    let previous = sa.author_id();
+    sa.set_author_id(author_id);
+    previous
+}
+
+/// A mapping from author IDs to display names
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registry {
+    names: BTreeMap<u32, String>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a display name, returning the name it replaces
+    pub fn insert(&mut self, author_id: u32, name: &str) -> Option<String> {
+        self.names.insert(author_id, name.trim().to_string())
+    }
+
+    pub fn remove(&mut self, author_id: u32) -> Option<String> {
+        self.names.remove(&author_id)
+    }
+
+    /// Returns the display name of a known author
+    pub fn name(&self, author_id: u32) -> Option<&str> {
+        self.names.get(&author_id).map(String::as_str)
+    }
+
+    /// Returns the display name of an author, or the ID itself when unknown
+    pub fn display(&self, author_id: u32) -> String {
+        match self.name(author_id) {
+            Some(name) => name.to_string(),
+            None => author_id.to_string(),
+        }
+    }
+
+    /// Iterates over the known authors in ascending ID order
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.names.iter().map(|(id, name)| (*id, name.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl fmt::Display for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (author_id, name) in self.iter() {
+            writeln!(f, "{author_id} {name}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Registry {
+    type Err = SARError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut registry = Registry::new();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || SARError::InvalidRegistry(format!("invalid line: {line}"));
+            let (author_id, name) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let author_id = author_id.parse().map_err(|_| invalid())?;
+            registry.insert(author_id, name);
+        }
+        Ok(registry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Compression, encode, parse, test::RAW_FILE};
+
+    #[test]
+    fn test_scrub() {
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let original = sa.author_id();
+
+        assert_eq!(scrub(&mut sa, ANONYMOUS), original);
+        let bytes = encode(&sa, Compression::Compressed).unwrap();
+        let scrubbed = parse(bytes).unwrap();
+        assert_eq!(scrubbed.author_id(), ANONYMOUS);
+        assert_eq!(scrubbed.layers(), sa.layers());
+    }
+
+    #[test]
+    fn test_registry_roundtrip() {
+        let mut registry = Registry::new();
+        registry.insert(7, "Matoi");
+        registry.insert(3, "Xiao the Second");
+
+        let text = registry.to_string();
+        assert_eq!(text, "3 Xiao the Second\n7 Matoi\n");
+        assert_eq!(text.parse::<Registry>().unwrap(), registry);
+        assert!("seven Matoi".parse::<Registry>().is_err());
+        assert!("7".parse::<Registry>().is_err());
+    }
+}
@@ -0,0 +1,376 @@
+//! Float-precision authoring
+//!
+//! Generated content such as gradients, text or vectorised images is naturally
+//! computed in `f32`, while layer corners are stored as `u8`. [`FloatLayer`]
+//! holds the corners in `f32`, and [`snap`] converts a whole set of layers into
+//! the integer [`Layer`] model at once, so that corners shared by adjacent layers
+//! always land on the same grid point and no seams appear between them.
+//!
+//! ```
+//! use sar_core::Layer;
+//! use sar_core::SymbolId;
+//! use sar_core::authoring::{FloatLayer, FloatPosition, Snap, snap};
+//!
+//! let quad = |x: f32| {
+//!     let vertices = [
+//!         FloatPosition::new(x, 100.2),
+//!         FloatPosition::new(x, 120.7),
+//!         FloatPosition::new(x + 10.4, 100.2),
+//!         FloatPosition::new(x + 10.4, 120.7),
+//!     ];
+//!     FloatLayer::new(Layer::new(SymbolId::new(241)), vertices)
+//! };
+//!
+//! let snapped = snap(&[quad(40.0), quad(50.4)], Snap::MinimizeError);
+//! assert_eq!(snapped.layers.len(), 2);
+//! // No corner moves further than to a neighbouring grid point
+//! assert!(snapped.report.max_error <= std::f32::consts::SQRT_2);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::core::sa::{CANVAS_SIZE, Position};
+use crate::parser::payload::Layer;
+
+/// Corners closer than this are treated as the same corner
+const SHARED_VERTEX_EPSILON: f32 = 1.0 / 1024.0;
+
+/// Represents a position in 2D space with sub-pixel precision
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatPosition {
+    /// X coordinate
+    pub x: f32,
+    /// Y coordinate
+    pub y: f32,
+}
+
+impl FloatPosition {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    fn distance(&self, other: &FloatPosition) -> f32 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
+    }
+}
+
+impl From<Position> for FloatPosition {
+    fn from(value: Position) -> Self {
+        Self {
+            x: value.x as f32,
+            y: value.y as f32,
+        }
+    }
+}
+
+/// A layer whose corners are positioned with sub-pixel precision
+///
+/// Everything but the corners, such as the symbol and color, is taken from the
+/// wrapped integer [`Layer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloatLayer {
+    layer: Layer,
+    vertices: [FloatPosition; 4],
+}
+
+impl FloatLayer {
+    /// Creates a layer from its attributes and corners
+    ///
+    /// The corners are in file order: top-left, bottom-left, top-right, bottom-right.
+    pub fn new(layer: Layer, vertices: [FloatPosition; 4]) -> Self {
+        Self { layer, vertices }
+    }
+
+    /// Returns the corners in file order: top-left, bottom-left, top-right, bottom-right
+    pub fn vertices(&self) -> [FloatPosition; 4] {
+        self.vertices
+    }
+
+    /// Sets the corners in file order: top-left, bottom-left, top-right, bottom-right
+    pub fn set_vertices(&mut self, vertices: [FloatPosition; 4]) {
+        self.vertices = vertices;
+    }
+
+    pub fn layer(&self) -> &Layer {
+        &self.layer
+    }
+
+    pub fn layer_mut(&mut self) -> &mut Layer {
+        &mut self.layer
+    }
+
+    /// Returns the area enclosed by the corners, see [`SymbolArtLayer::area`](crate::SymbolArtLayer::area)
+    fn area_with(vertices: &[FloatPosition; 4]) -> f32 {
+        // file order is TL, BL, TR, BR; walk TL, TR, BR, BL
+        let points = [vertices[0], vertices[2], vertices[3], vertices[1]];
+        let twice_area = (0..4)
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % 4]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum::<f32>();
+        twice_area.abs() / 2.0
+    }
+
+    fn centroid(&self) -> FloatPosition {
+        let (x, y) = self
+            .vertices
+            .iter()
+            .fold((0.0, 0.0), |(x, y), v| (x + v.x, y + v.y));
+        FloatPosition::new(x / 4.0, y / 4.0)
+    }
+}
+
+impl From<&Layer> for FloatLayer {
+    fn from(layer: &Layer) -> Self {
+        Self {
+            layer: *layer,
+            vertices: layer.vertices().map(FloatPosition::from),
+        }
+    }
+}
+
+/// How float corners are snapped onto the integer coordinate grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Snap {
+    /// Rounds every corner to the nearest grid point
+    Round,
+    /// Rounds every corner toward the centroid of its layer, so layers never grow
+    ///
+    /// Corners shared with another layer are rounded to the nearest grid point
+    /// instead, as there is no single centroid to round toward.
+    TowardCentroid,
+    /// Picks, for every corner, the neighbouring grid point that best preserves
+    /// the area of the layers sharing it
+    MinimizeError,
+}
+
+/// Statistics about the error introduced by snapping
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SnapReport {
+    /// The largest distance a corner was moved by
+    pub max_error: f32,
+    /// The average distance a corner was moved by
+    pub mean_error: f32,
+    /// The index of the layer holding the corner that moved the most
+    pub max_error_layer: Option<usize>,
+}
+
+/// The integer layers produced by [`snap`]
+#[derive(Debug, Clone)]
+pub struct Snapped {
+    /// The snapped layers, in the same order as the input
+    pub layers: Vec<Layer>,
+    /// The error introduced by snapping
+    pub report: SnapReport,
+}
+
+/// Snaps float layers onto the integer coordinate grid
+///
+/// Corners within a 1/1024 pixel of each other are snapped as one, so edges
+/// shared by adjacent layers stay aligned whichever strategy is used. Corners
+/// are clamped to the coordinate space.
+pub fn snap(layers: &[FloatLayer], strategy: Snap) -> Snapped {
+    // Group corners shared between layers
+    let mut groups: HashMap<(i64, i64), usize> = HashMap::new();
+    let mut members: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut points: Vec<FloatPosition> = Vec::new();
+    for (i, layer) in layers.iter().enumerate() {
+        for (j, vertex) in layer.vertices.iter().enumerate() {
+            let key = (
+                (vertex.x / SHARED_VERTEX_EPSILON).round() as i64,
+                (vertex.y / SHARED_VERTEX_EPSILON).round() as i64,
+            );
+            let group = *groups.entry(key).or_insert_with(|| {
+                members.push(Vec::new());
+                points.push(*vertex);
+                members.len() - 1
+            });
+            members[group].push((i, j));
+        }
+    }
+
+    let mut snapped = points
+        .iter()
+        .enumerate()
+        .map(|(group, point)| match (strategy, &members[group][..]) {
+            (Snap::TowardCentroid, [(i, _)]) => {
+                let centroid = layers[*i].centroid();
+                FloatPosition::new(toward(point.x, centroid.x), toward(point.y, centroid.y))
+            }
+            _ => FloatPosition::new(point.x.round(), point.y.round()),
+        })
+        .map(clamp)
+        .collect::<Vec<_>>();
+
+    if strategy == Snap::MinimizeError {
+        minimize_area_error(layers, &points, &members, &mut snapped);
+    }
+
+    // Resolve the snapped corners of every layer
+    let mut vertices = layers
+        .iter()
+        .map(|layer| layer.vertices)
+        .collect::<Vec<_>>();
+    for (group, group_members) in members.iter().enumerate() {
+        for (i, j) in group_members {
+            vertices[*i][*j] = snapped[group];
+        }
+    }
+
+    let mut report = SnapReport::default();
+    let mut total_error = 0.0;
+    let mut count = 0;
+    let layers = layers
+        .iter()
+        .zip(&vertices)
+        .enumerate()
+        .map(|(i, (layer, snapped))| {
+            for (original, snapped) in layer.vertices.iter().zip(snapped) {
+                let error = original.distance(snapped);
+                total_error += error;
+                count += 1;
+                if error > report.max_error || report.max_error_layer.is_none() {
+                    report.max_error = error;
+                    report.max_error_layer = Some(i);
+                }
+            }
+
+            let mut result = layer.layer;
+            result.set_vertices(snapped.map(|v| Position {
+                x: v.x as u8,
+                y: v.y as u8,
+            }));
+            result
+        })
+        .collect();
+    if count > 0 {
+        report.mean_error = total_error / count as f32;
+    }
+
+    Snapped { layers, report }
+}
+
+/// Rounds `value` to the grid point nearest to it on the side of `target`
+fn toward(value: f32, target: f32) -> f32 {
+    if value < target {
+        value.ceil()
+    } else {
+        value.floor()
+    }
+}
+
+fn clamp(position: FloatPosition) -> FloatPosition {
+    let max = (CANVAS_SIZE - 1) as f32;
+    FloatPosition::new(position.x.clamp(0.0, max), position.y.clamp(0.0, max))
+}
+
+/// Refines rounded corners one group at a time, choosing among the four
+/// surrounding grid points the one that minimises the area error of the layers
+/// sharing the corner, then the distance moved.
+fn minimize_area_error(
+    layers: &[FloatLayer],
+    points: &[FloatPosition],
+    members: &[Vec<(usize, usize)>],
+    snapped: &mut [FloatPosition],
+) {
+    let mut current = layers
+        .iter()
+        .map(|layer| layer.vertices)
+        .collect::<Vec<_>>();
+    for (group, group_members) in members.iter().enumerate() {
+        for (i, j) in group_members {
+            current[*i][*j] = snapped[group];
+        }
+    }
+    let target_areas = layers
+        .iter()
+        .map(|layer| FloatLayer::area_with(&layer.vertices))
+        .collect::<Vec<_>>();
+
+    for (group, point) in points.iter().enumerate() {
+        let candidates = [
+            FloatPosition::new(point.x.floor(), point.y.floor()),
+            FloatPosition::new(point.x.ceil(), point.y.floor()),
+            FloatPosition::new(point.x.floor(), point.y.ceil()),
+            FloatPosition::new(point.x.ceil(), point.y.ceil()),
+        ];
+
+        let cost = |candidate: &FloatPosition, current: &mut Vec<[FloatPosition; 4]>| {
+            let mut area_error = 0.0;
+            for (i, j) in &members[group] {
+                current[*i][*j] = *candidate;
+                area_error += (FloatLayer::area_with(&current[*i]) - target_areas[*i]).abs();
+            }
+            (area_error, point.distance(candidate))
+        };
+
+        let best = candidates
+            .iter()
+            .map(|candidate| clamp(*candidate))
+            .map(|candidate| (cost(&candidate, &mut current), candidate))
+            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(_, candidate)| candidate)
+            .unwrap_or(snapped[group]);
+
+        snapped[group] = best;
+        for (i, j) in &members[group] {
+            current[*i][*j] = best;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SymbolId, core::sa::SymbolArt, parse, test::RAW_FILE};
+
+    fn quad(left: f32, top: f32, right: f32, bottom: f32) -> FloatLayer {
+        FloatLayer::new(
+            Layer::new(SymbolId::new(241)),
+            [
+                FloatPosition::new(left, top),
+                FloatPosition::new(left, bottom),
+                FloatPosition::new(right, top),
+                FloatPosition::new(right, bottom),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_snap_integer_layers_is_lossless() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let layers = sa.layers().iter().map(FloatLayer::from).collect::<Vec<_>>();
+
+        for strategy in [Snap::Round, Snap::TowardCentroid, Snap::MinimizeError] {
+            let snapped = snap(&layers, strategy);
+            assert_eq!(snapped.layers, sa.layers());
+            assert_eq!(snapped.report.max_error, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_snap_keeps_shared_edges() {
+        let layers = [quad(10.3, 10.6, 20.5, 30.5), quad(20.5, 10.6, 30.7, 30.5)];
+
+        for strategy in [Snap::Round, Snap::TowardCentroid, Snap::MinimizeError] {
+            let snapped = snap(&layers, strategy);
+            let (left, right) = (&snapped.layers[0], &snapped.layers[1]);
+            let [_, _, left_top_right, left_bottom_right] = left.vertices();
+            let [right_top_left, right_bottom_left, _, _] = right.vertices();
+            assert_eq!(left_top_right, right_top_left);
+            assert_eq!(left_bottom_right, right_bottom_left);
+            assert!(snapped.report.max_error < 1.5);
+        }
+    }
+
+    #[test]
+    fn test_snap_toward_centroid_shrinks() {
+        let layers = [quad(10.5, 10.5, 20.5, 20.5)];
+        let snapped = snap(&layers, Snap::TowardCentroid);
+        let [top_left, _, _, bottom_right] = snapped.layers[0].vertices();
+        assert_eq!(top_left, Position { x: 11, y: 11 });
+        assert_eq!(bottom_right, Position { x: 20, y: 20 });
+        assert_eq!(snapped.report.max_error_layer, Some(0));
+    }
+}
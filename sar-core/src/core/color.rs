@@ -0,0 +1,260 @@
+use super::sa::{ALPHA_LEVELS, COLOR_LEVELS, Color, RawColor};
+
+/// The space in which distances between colors are measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// Euclidean distance between gamma-encoded sRGB components
+    #[default]
+    Srgb,
+    /// Euclidean distance in CIELAB (CIE76 delta E), closer to perceived difference
+    Lab,
+}
+
+/// The result of quantizing a color into the values a SAR file can store
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantized {
+    /// The quantized color as stored in the file
+    pub color: RawColor,
+    /// The distance between the original and the expanded quantized color,
+    /// measured in the requested color space and ignoring alpha
+    pub error: f32,
+    /// The absolute difference between the original and the expanded alpha
+    pub alpha_error: u8,
+}
+
+impl Color {
+    /// Quantizes the color to the nearest storable color in sRGB
+    ///
+    /// This is the exact inverse of [`RawColor::expand`]: quantizing an expanded
+    /// color gives back the same raw color with no error.
+    pub fn quantize(&self) -> Quantized {
+        self.quantize_in(ColorSpace::Srgb)
+    }
+
+    /// Quantizes the color to the nearest storable color in the given color space
+    pub fn quantize_in(&self, space: ColorSpace) -> Quantized {
+        let a = nearest_level(&ALPHA_LEVELS, self.a);
+        let alpha_error = self.a.abs_diff(ALPHA_LEVELS[a as usize]);
+
+        // In sRGB the channels are independent, so the nearest level of each
+        // channel gives the nearest color. Otherwise the two nearest levels of
+        // every channel are tried.
+        let candidates = |value: u8| {
+            let nearest = nearest_level(&COLOR_LEVELS, value);
+            let other = if COLOR_LEVELS[nearest as usize] < value {
+                nearest.saturating_add(1).min(63)
+            } else {
+                nearest.saturating_sub(1)
+            };
+            match space {
+                ColorSpace::Srgb => vec![nearest],
+                ColorSpace::Lab => vec![nearest, other],
+            }
+        };
+
+        let mut best: Option<Quantized> = None;
+        for r in candidates(self.r) {
+            for g in candidates(self.g) {
+                for b in candidates(self.b) {
+                    let color = RawColor::new(a, r, g, b);
+                    let error = self.distance_in(&color.expand(), space);
+                    if best.is_none_or(|best| error < best.error) {
+                        best = Some(Quantized {
+                            color,
+                            error,
+                            alpha_error,
+                        });
+                    }
+                }
+            }
+        }
+
+        // There is always at least one candidate per channel
+        best.unwrap()
+    }
+
+    /// Returns the distance between two colors in the given color space
+    ///
+    /// The alpha channel is ignored.
+    pub fn distance_in(&self, other: &Color, space: ColorSpace) -> f32 {
+        match space {
+            ColorSpace::Srgb => self.distance(other),
+            ColorSpace::Lab => {
+                let [l1, a1, b1] = self.to_lab();
+                let [l2, a2, b2] = other.to_lab();
+                ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+            }
+        }
+    }
+
+    /// Converts the color to CIELAB under the D65 white point
+    pub fn to_lab(&self) -> [f32; 3] {
+        let [r, g, b] = [self.r, self.g, self.b].map(srgb_to_linear);
+
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+        let f = |t: f32| {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+    }
+
+    /// Converts the color to hue (degrees), saturation and lightness (between 0 and 1)
+    pub fn to_hsl(&self) -> [f32; 3] {
+        let [r, g, b] = [self.r, self.g, self.b].map(|v| v as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lightness = (max + min) / 2.0;
+        let delta = max - min;
+        if delta == 0.0 {
+            return [0.0, 0.0, lightness];
+        }
+
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        [hue, saturation, lightness]
+    }
+
+    /// Creates a color from hue (degrees), saturation and lightness (between 0 and 1)
+    pub fn from_hsl(a: u8, hue: f32, saturation: f32, lightness: f32) -> Self {
+        let hue = hue.rem_euclid(360.0);
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let x = chroma * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+        let m = lightness - chroma / 2.0;
+        let (r, g, b) = match hue as u32 / 60 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let to_u8 = |v: f32| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color::new(a, to_u8(r), to_u8(g), to_u8(b))
+    }
+
+    /// Parses a color from `rrggbb` or `rrggbbaa` hex notation, with an optional leading `#`
+    ///
+    /// The color is fully opaque when the alpha is omitted.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        let a = if hex.len() == 8 { channel(6)? } else { 255 };
+        Some(Color::new(a, channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    /// Formats the color as `rrggbb` hex notation, ignoring alpha
+    pub fn to_hex(&self) -> String {
+        format!("{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// Converts a gamma-encoded sRGB component to linear light between 0 and 1
+pub(crate) fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts linear light between 0 and 1 to a gamma-encoded sRGB component
+pub(crate) fn linear_to_srgb(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let value = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (value * 255.0).round() as u8
+}
+
+/// Returns the index of the level closest to `value`, preferring the lower one on ties
+fn nearest_level(levels: &[u8], value: u8) -> u8 {
+    let mut nearest = 0;
+    for (i, level) in levels.iter().enumerate() {
+        if level.abs_diff(value) < levels[nearest].abs_diff(value) {
+            nearest = i;
+        }
+    }
+    nearest as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantize_roundtrip() {
+        for a in 0..8 {
+            for level in 0..64 {
+                let raw = RawColor::new(a, level, 63 - level, level / 2);
+                for space in [ColorSpace::Srgb, ColorSpace::Lab] {
+                    let quantized = raw.expand().quantize_in(space);
+                    assert_eq!(quantized.color, raw);
+                    assert_eq!(quantized.error, 0.0);
+                    assert_eq!(quantized.alpha_error, 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_quantize_nearest() {
+        let quantized = Color::new(200, 255, 2, 129).quantize();
+        assert_eq!(quantized.color, RawColor::new(5, 63, 0, 32));
+        assert_eq!(quantized.alpha_error, 18);
+        assert!(quantized.error > 0.0);
+    }
+
+    #[test]
+    fn test_hsl_roundtrip() {
+        for color in [
+            Color::new(255, 255, 0, 0),
+            Color::new(255, 12, 200, 99),
+            Color::new(255, 128, 128, 128),
+        ] {
+            let [h, s, l] = color.to_hsl();
+            assert_eq!(Color::from_hsl(color.a, h, s, l), color);
+        }
+    }
+
+    #[test]
+    fn test_hex() {
+        let color = Color::from_hex("#ff8000").unwrap();
+        assert_eq!(color, Color::new(255, 255, 128, 0));
+        assert_eq!(color.to_hex(), "ff8000");
+        assert_eq!(Color::from_hex("ff800080").unwrap().a, 0x80);
+        assert!(Color::from_hex("ff80").is_none());
+        assert!(Color::from_hex("gg0000").is_none());
+    }
+
+    #[test]
+    fn test_quantize_lab_is_no_worse_perceptually() {
+        let color = Color::new(255, 37, 181, 90);
+        let srgb = color.quantize_in(ColorSpace::Srgb);
+        let lab = color.quantize_in(ColorSpace::Lab);
+        let srgb_error = color.distance_in(&srgb.color.expand(), ColorSpace::Lab);
+        assert!(lab.error <= srgb_error);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod color;
 pub mod result;
 pub mod sa;
 pub mod symbol;
@@ -19,4 +19,38 @@ pub enum SARError {
     ImageError(#[from] image::ImageError),
     #[error("failed to create projection for points: from {0:?} to {1:?}")]
     ProjectionError([(f32, f32); 4], [(f32, f32); 4]),
+    #[error("too many layers: {count} exceeds the limit of {limit} by {}", count - limit)]
+    LayerLimitExceeded { count: usize, limit: usize },
+    #[error("position out of range: ({0}, {1})")]
+    PositionOutOfRange(i32, i32),
+    #[error("invalid layout: {0}")]
+    InvalidLayout(String),
+    #[error("invalid author registry: {0}")]
+    InvalidRegistry(String),
+    #[error("invalid digest: {0}")]
+    InvalidDigest(String),
+    #[error("invalid severity: {0}")]
+    InvalidSeverity(String),
+    #[error("unsupported canvas: {0}")]
+    UnsupportedCanvas(String),
+}
+
+impl SARError {
+    /// Returns a short kebab-case name for the kind of error, without its details
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SARError::InvalidFileHeader => "invalid-file-header",
+            SARError::IoError(_) => "io",
+            SARError::SymbolNotFound(_) => "symbol-not-found",
+            SARError::ImageError(_) => "image",
+            SARError::ProjectionError(..) => "projection",
+            SARError::LayerLimitExceeded { .. } => "layer-limit-exceeded",
+            SARError::PositionOutOfRange(..) => "position-out-of-range",
+            SARError::InvalidLayout(_) => "invalid-layout",
+            SARError::InvalidRegistry(_) => "invalid-registry",
+            SARError::InvalidDigest(_) => "invalid-digest",
+            SARError::InvalidSeverity(_) => "invalid-severity",
+            SARError::UnsupportedCanvas(_) => "unsupported-canvas",
+        }
+    }
 }
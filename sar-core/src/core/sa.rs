@@ -2,8 +2,57 @@ use std::fmt::Debug;
 
 use super::symbol::Symbol;
 
+/// The maximum number of layers a SymbolArt can have in game
+pub const MAX_LAYERS: usize = 225;
+
+/// The size of the coordinate space layers are positioned in
+pub const CANVAS_SIZE: u32 = 256;
+
+/// The kinds of canvas a SymbolArt can be drawn on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Canvas {
+    /// The regular 193x96 SymbolArt canvas
+    Standard,
+    /// The 32x32 alliance (team) flag canvas
+    TeamFlag,
+}
+
+impl Canvas {
+    pub fn width(&self) -> u8 {
+        match self {
+            Canvas::Standard => 193,
+            Canvas::TeamFlag => 32,
+        }
+    }
+
+    pub fn height(&self) -> u8 {
+        match self {
+            Canvas::Standard => 96,
+            Canvas::TeamFlag => 32,
+        }
+    }
+
+    /// Returns the visible area of the canvas within the coordinate space
+    ///
+    /// The visible area is centered the same way the drawer centers it.
+    pub fn view(&self) -> Rect {
+        let left = (CANVAS_SIZE / 2 - self.width() as u32 / 2) as u8;
+        let top = (CANVAS_SIZE / 2 - self.height() as u32 / 2) as u8;
+        Rect::new(
+            Position { x: left, y: top },
+            Position {
+                x: left + self.width() - 1,
+                y: top + self.height() - 1,
+            },
+        )
+    }
+}
+
 /// Represents a position in 2D space
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Position {
     /// X coordinate
     pub x: u8,
@@ -11,6 +60,53 @@ pub struct Position {
     pub y: u8,
 }
 
+/// Represents an axis-aligned rectangle in SymbolArt coordinates
+///
+/// Both corners are inclusive, so a rectangle whose `min` equals its `max`
+/// covers a single point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    /// Top-left corner
+    pub min: Position,
+    /// Bottom-right corner
+    pub max: Position,
+}
+
+impl Rect {
+    pub fn new(min: Position, max: Position) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns the smallest rectangle enclosing all the given points
+    pub fn enclosing(points: &[Position]) -> Self {
+        let min = Position {
+            x: points.iter().map(|p| p.x).min().unwrap_or(0),
+            y: points.iter().map(|p| p.y).min().unwrap_or(0),
+        };
+        let max = Position {
+            x: points.iter().map(|p| p.x).max().unwrap_or(0),
+            y: points.iter().map(|p| p.y).max().unwrap_or(0),
+        };
+        Self { min, max }
+    }
+
+    /// Returns true if the two rectangles share at least one point
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// Returns true if `other` lies entirely within this rectangle
+    pub fn contains(&self, other: &Rect) -> bool {
+        self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && other.max.x <= self.max.x
+            && other.max.y <= self.max.y
+    }
+}
+
 /// Represents a complete SymbolArt composition
 ///
 /// A SymbolArt is a user-created artwork composed of multiple layers of symbols.
@@ -22,6 +118,23 @@ pub trait SymbolArt: Send + Sync {
     fn width(&self) -> u8;
     fn layers(&self) -> Vec<Self::Layer>;
     fn name(&self) -> String;
+
+    /// Returns the canvas the art is drawn on, or `None` when it is unknown
+    ///
+    /// By default the canvas is matched from [`width`](Self::width) and
+    /// [`height`](Self::height).
+    fn canvas(&self) -> Option<Canvas> {
+        [Canvas::Standard, Canvas::TeamFlag]
+            .into_iter()
+            .find(|canvas| (canvas.width(), canvas.height()) == (self.width(), self.height()))
+    }
+
+    /// Returns the sound effect played when the art is shown
+    ///
+    /// Defaults to 0 for arts that do not store one.
+    fn sound_effect(&self) -> u8 {
+        0
+    }
 }
 
 /// Represents a single layer in a SymbolArt composition
@@ -36,8 +149,47 @@ pub trait SymbolArtLayer {
     fn top_right(&self) -> Position;
     fn bottom_right(&self) -> Position;
     fn symbol(&self) -> Symbol;
+    /// Returns the color as displayed in game
     fn color(&self) -> Color;
     fn is_hidden(&self) -> bool;
+
+    /// Returns the color as stored in the file, before expansion to 8 bits
+    ///
+    /// By default the color is quantized, which gives back the stored levels of
+    /// any color produced by [`RawColor::expand`].
+    fn raw_color(&self) -> RawColor {
+        self.color().quantize().color
+    }
+
+    /// Returns the axis-aligned bounding box of the layer's four corners
+    fn bounding_box(&self) -> Rect {
+        Rect::enclosing(&[
+            self.top_left(),
+            self.bottom_left(),
+            self.top_right(),
+            self.bottom_right(),
+        ])
+    }
+
+    /// Returns the area enclosed by the layer's four corners
+    ///
+    /// The corners are walked top-left, top-right, bottom-right, bottom-left, so
+    /// a degenerate quad has an area of zero.
+    fn area(&self) -> f32 {
+        let points = [
+            self.top_left(),
+            self.top_right(),
+            self.bottom_right(),
+            self.bottom_left(),
+        ];
+        let twice_area = (0..4)
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % 4]);
+                a.x as i32 * b.y as i32 - b.x as i32 * a.y as i32
+            })
+            .sum::<i32>();
+        twice_area.abs() as f32 / 2.0
+    }
 }
 
 /// Represents a color in RGBA format
@@ -45,7 +197,7 @@ pub trait SymbolArtLayer {
 /// Each component (red, green, blue, alpha) is represented as an 8-bit unsigned integer,
 /// allowing for values between 0 and 255. The alpha channel controls transparency,
 /// where 0 is fully transparent and 255 is fully opaque.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Color {
     pub a: u8,
     pub r: u8,
@@ -57,6 +209,81 @@ impl Color {
     pub fn new(a: u8, r: u8, g: u8, b: u8) -> Self {
         Self { a, r, g, b }
     }
+
+    /// Returns the Euclidean distance between two colors in RGB space
+    ///
+    /// The alpha channel is ignored.
+    pub fn distance(&self, other: &Color) -> f32 {
+        let dr = self.r as f32 - other.r as f32;
+        let dg = self.g as f32 - other.g as f32;
+        let db = self.b as f32 - other.b as f32;
+        (dr * dr + dg * dg + db * db).sqrt()
+    }
+}
+
+/// The 8-bit values of the 64 color levels a SAR file can store
+///
+/// The 6-bit value is expanded by replicating its high bits into the low bits,
+/// so level 0 is 0, level 63 is 255, and every level maps to a distinct value.
+pub const COLOR_LEVELS: [u8; 64] = expand_levels::<64>(6);
+
+/// The 8-bit values of the 8 alpha levels a SAR file can store
+///
+/// The 3-bit value is expanded the same way as [`COLOR_LEVELS`], so level 0 is
+/// fully transparent and level 7 is fully opaque.
+pub const ALPHA_LEVELS: [u8; 8] = expand_levels::<8>(3);
+
+/// Expands every `bits` wide value to 8 bits by bit replication
+const fn expand_levels<const N: usize>(bits: u32) -> [u8; N] {
+    let mut levels = [0; N];
+    let mut i = 0;
+    while i < N {
+        let mut value = (i as u32) << (8 - bits);
+        let mut filled = bits;
+        while filled < 8 {
+            value |= value >> filled;
+            filled *= 2;
+        }
+        levels[i] = value as u8;
+        i += 1;
+    }
+    levels
+}
+
+/// Represents a color as stored in a SAR file
+///
+/// The red, green and blue components are 6-bit values between 0 and 63, and
+/// the alpha channel is a 3-bit value between 0 and 7. Out of range bits are
+/// ignored when expanding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RawColor {
+    pub a: u8,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl RawColor {
+    pub fn new(a: u8, r: u8, g: u8, b: u8) -> Self {
+        Self { a, r, g, b }
+    }
+
+    /// Expands the color to 8 bits per channel, as displayed in game
+    pub fn expand(&self) -> Color {
+        Color::new(
+            ALPHA_LEVELS[(self.a & 0x07) as usize],
+            COLOR_LEVELS[(self.r & 0x3f) as usize],
+            COLOR_LEVELS[(self.g & 0x3f) as usize],
+            COLOR_LEVELS[(self.b & 0x3f) as usize],
+        )
+    }
+}
+
+impl From<RawColor> for Color {
+    fn from(value: RawColor) -> Self {
+        value.expand()
+    }
 }
 
 impl From<Color> for image::Rgba<u8> {
@@ -64,3 +291,69 @@ impl From<Color> for image::Rgba<u8> {
         image::Rgba([value.r, value.g, value.b, value.a])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::symbol::SymbolId;
+
+    #[test]
+    fn test_color_levels() {
+        assert_eq!(
+            COLOR_LEVELS,
+            [
+                0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 44, 48, 52, 56, 60, 65, 69, 73, 77, 81,
+                85, 89, 93, 97, 101, 105, 109, 113, 117, 121, 125, 130, 134, 138, 142, 146, 150,
+                154, 158, 162, 166, 170, 174, 178, 182, 186, 190, 195, 199, 203, 207, 211, 215,
+                219, 223, 227, 231, 235, 239, 243, 247, 251, 255,
+            ]
+        );
+        assert!(COLOR_LEVELS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_alpha_levels() {
+        assert_eq!(ALPHA_LEVELS, [0, 36, 73, 109, 146, 182, 219, 255]);
+        assert!(ALPHA_LEVELS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_raw_color_expand() {
+        let color = RawColor::new(7, 63, 32, 0).expand();
+        assert_eq!(color, Color::new(255, 255, 130, 0));
+        assert_ne!(RawColor::new(6, 0, 0, 0).expand().a, color.a);
+    }
+
+    #[test]
+    fn test_default_raw_color() {
+        // A layer implementing only the required methods
+        struct Solid(Color);
+
+        impl SymbolArtLayer for Solid {
+            fn top_left(&self) -> Position {
+                Position { x: 0, y: 0 }
+            }
+            fn bottom_left(&self) -> Position {
+                Position { x: 0, y: 0 }
+            }
+            fn top_right(&self) -> Position {
+                Position { x: 0, y: 0 }
+            }
+            fn bottom_right(&self) -> Position {
+                Position { x: 0, y: 0 }
+            }
+            fn symbol(&self) -> Symbol {
+                Symbol::new(SymbolId::new(0))
+            }
+            fn color(&self) -> Color {
+                self.0
+            }
+            fn is_hidden(&self) -> bool {
+                false
+            }
+        }
+
+        let raw = RawColor::new(5, 63, 32, 1);
+        assert_eq!(Solid(raw.expand()).raw_color(), raw);
+    }
+}
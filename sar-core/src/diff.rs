@@ -0,0 +1,552 @@
+//! Structural comparison of two SymbolArts
+//!
+//! [`diff`] aligns the layer sequences of two arts the way a text diff aligns
+//! lines, so an inserted layer shows as one addition rather than a change to
+//! every layer below it. Layers are reported as added, removed, moved or
+//! changed, with the fields that changed, next to any metadata changes.
+//!
+//! ```
+//! use sar_core::{diff::diff, parse};
+//!
+//! let bytes = include_bytes!("../../fixture/sa0a1d081b8a108bb8c9847c4cd83db662.sar");
+//! let before = parse(Vec::from(*bytes)).unwrap();
+//! let mut after = before.clone();
+//! after.layers_mut().remove(0);
+//!
+//! let diff = diff(&before, &after);
+//! assert_eq!(diff.layers.len(), 1);
+//! println!("{}", diff.layers[0]);
+//! ```
+
+use std::fmt;
+
+use crate::core::{
+    sa::{Canvas, Position, RawColor, SymbolArt, SymbolArtLayer},
+    symbol::SymbolId,
+};
+use crate::parser::payload::Payload;
+
+/// The number of fields two layers may differ in to count as one changed layer
+const MAX_CHANGED_FIELDS: usize = 2;
+
+/// The stored properties of a layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LayerState {
+    /// The corners in file order: top-left, bottom-left, top-right, bottom-right
+    pub vertices: [Position; 4],
+    pub symbol: SymbolId,
+    pub color: RawColor,
+    pub hidden: bool,
+}
+
+impl LayerState {
+    /// Captures the properties of a layer
+    pub fn of<L: SymbolArtLayer>(layer: &L) -> Self {
+        Self {
+            vertices: [
+                layer.top_left(),
+                layer.bottom_left(),
+                layer.top_right(),
+                layer.bottom_right(),
+            ],
+            symbol: layer.symbol().id(),
+            color: layer.raw_color(),
+            hidden: layer.is_hidden(),
+        }
+    }
+
+    /// Lists the fields that differ from `other`
+    fn changes(&self, other: &LayerState) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
+        if self.vertices != other.vertices {
+            changes.push(FieldChange::Vertices {
+                before: self.vertices,
+                after: other.vertices,
+            });
+        }
+        if self.color != other.color {
+            changes.push(FieldChange::Color {
+                before: self.color,
+                after: other.color,
+            });
+        }
+        if self.symbol != other.symbol {
+            changes.push(FieldChange::Symbol {
+                before: self.symbol,
+                after: other.symbol,
+            });
+        }
+        if self.hidden != other.hidden {
+            changes.push(FieldChange::Hidden {
+                before: self.hidden,
+                after: other.hidden,
+            });
+        }
+        changes
+    }
+}
+
+impl fmt::Display for LayerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "symbol {} {} at {}",
+            self.symbol,
+            DisplayColor(self.color),
+            DisplayVertices(self.vertices)
+        )?;
+        if self.hidden {
+            write!(f, " (hidden)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A field that differs between two aligned layers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "field", rename_all = "kebab-case"))]
+pub enum FieldChange {
+    Vertices {
+        before: [Position; 4],
+        after: [Position; 4],
+    },
+    Color {
+        before: RawColor,
+        after: RawColor,
+    },
+    Symbol {
+        before: SymbolId,
+        after: SymbolId,
+    },
+    Hidden {
+        before: bool,
+        after: bool,
+    },
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldChange::Vertices { before, after } => write!(
+                f,
+                "vertices {} -> {}",
+                DisplayVertices(*before),
+                DisplayVertices(*after)
+            ),
+            FieldChange::Color { before, after } => write!(
+                f,
+                "color {} -> {}",
+                DisplayColor(*before),
+                DisplayColor(*after)
+            ),
+            FieldChange::Symbol { before, after } => write!(f, "symbol {before} -> {after}"),
+            FieldChange::Hidden { before, after } => write!(f, "hidden {before} -> {after}"),
+        }
+    }
+}
+
+/// A difference in the layer sequences
+///
+/// Indices count from the top: `from` and removed indices refer to the first
+/// art, `to` and added indices to the second.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "kebab-case"))]
+pub enum LayerDiff {
+    /// The layer only exists in the second art
+    Added { index: usize, layer: LayerState },
+    /// The layer only exists in the first art
+    Removed { index: usize, layer: LayerState },
+    /// The layer is unchanged but moved relative to the other layers
+    Moved { from: usize, to: usize },
+    /// The layer stayed in place but some of its fields changed
+    Changed {
+        from: usize,
+        to: usize,
+        changes: Vec<FieldChange>,
+    },
+}
+
+impl LayerDiff {
+    /// Orders the differences as they appear from the top of the second art
+    fn sort_key(&self) -> (usize, bool) {
+        match self {
+            LayerDiff::Removed { index, .. } => (*index, false),
+            LayerDiff::Added { index, .. } => (*index, true),
+            LayerDiff::Moved { to, .. } | LayerDiff::Changed { to, .. } => (*to, true),
+        }
+    }
+}
+
+impl fmt::Display for LayerDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayerDiff::Added { index, layer } => write!(f, "+ layer {index}: {layer}"),
+            LayerDiff::Removed { index, layer } => write!(f, "- layer {index}: {layer}"),
+            LayerDiff::Moved { from, to } => write!(f, "> layer {from} -> {to}"),
+            LayerDiff::Changed { from, to, changes } => {
+                write!(f, "~ layer {from} -> {to}:")?;
+                for (i, change) in changes.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { "; " };
+                    write!(f, "{separator}{change}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A difference in the properties of the arts themselves
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "field", rename_all = "kebab-case"))]
+pub enum MetadataChange {
+    Name {
+        before: String,
+        after: String,
+    },
+    Author {
+        before: u32,
+        after: u32,
+    },
+    /// The canvas described by the header, `None` when it is unknown
+    ///
+    /// The header bytes are compared, so two different unknown canvases are
+    /// reported as a change from `None` to `None`.
+    Canvas {
+        before: Option<Canvas>,
+        after: Option<Canvas>,
+    },
+    SoundEffect {
+        before: u8,
+        after: u8,
+    },
+}
+
+impl fmt::Display for MetadataChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataChange::Name { before, after } => write!(f, "name {before:?} -> {after:?}"),
+            MetadataChange::Author { before, after } => write!(f, "author {before} -> {after}"),
+            MetadataChange::Canvas { before, after } => {
+                write!(
+                    f,
+                    "canvas {} -> {}",
+                    canvas_name(before),
+                    canvas_name(after)
+                )
+            }
+            MetadataChange::SoundEffect { before, after } => {
+                write!(f, "sound effect {before} -> {after}")
+            }
+        }
+    }
+}
+
+fn canvas_name(canvas: &Option<Canvas>) -> &'static str {
+    match canvas {
+        Some(Canvas::Standard) => "standard",
+        Some(Canvas::TeamFlag) => "team-flag",
+        None => "unknown",
+    }
+}
+
+/// The differences between two SymbolArts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Diff {
+    pub metadata: Vec<MetadataChange>,
+    /// The layer differences, from the top of the second art
+    pub layers: Vec<LayerDiff>,
+    /// The number of layers identical and in the same order in both arts
+    pub unchanged: usize,
+}
+
+impl Diff {
+    /// Returns whether the arts are identical
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty() && self.layers.is_empty()
+    }
+}
+
+/// Compares two SymbolArts
+///
+/// The longest run of identical layers in the same order is kept as unchanged.
+/// Identical layers outside it are moves. The remaining layers are aligned
+/// again, pairing layers that differ in at most two fields as changes, and
+/// whatever is left is added or removed.
+pub fn diff(before: &Payload, after: &Payload) -> Diff {
+    let mut metadata = Vec::new();
+    if before.name() != after.name() {
+        metadata.push(MetadataChange::Name {
+            before: before.name(),
+            after: after.name(),
+        });
+    }
+    if before.author_id() != after.author_id() {
+        metadata.push(MetadataChange::Author {
+            before: before.author_id(),
+            after: after.author_id(),
+        });
+    }
+    if before.canvas_bytes() != after.canvas_bytes() {
+        metadata.push(MetadataChange::Canvas {
+            before: before.canvas(),
+            after: after.canvas(),
+        });
+    }
+    if before.sound_effect() != after.sound_effect() {
+        metadata.push(MetadataChange::SoundEffect {
+            before: before.sound_effect(),
+            after: after.sound_effect(),
+        });
+    }
+
+    let a = before
+        .layers()
+        .iter()
+        .map(LayerState::of)
+        .collect::<Vec<_>>();
+    let b = after
+        .layers()
+        .iter()
+        .map(LayerState::of)
+        .collect::<Vec<_>>();
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut layers = Vec::new();
+
+    let all_a = (0..a.len()).collect::<Vec<_>>();
+    let all_b = (0..b.len()).collect::<Vec<_>>();
+    let unchanged = align(&all_a, &all_b, |i, j| a[i] == b[j]);
+    for &(i, j) in &unchanged {
+        (a_matched[i], b_matched[j]) = (true, true);
+    }
+
+    for i in 0..a.len() {
+        if a_matched[i] {
+            continue;
+        }
+        if let Some(j) = (0..b.len()).find(|&j| !b_matched[j] && a[i] == b[j]) {
+            (a_matched[i], b_matched[j]) = (true, true);
+            layers.push(LayerDiff::Moved { from: i, to: j });
+        }
+    }
+
+    let rest_a = (0..a.len()).filter(|&i| !a_matched[i]).collect::<Vec<_>>();
+    let rest_b = (0..b.len()).filter(|&j| !b_matched[j]).collect::<Vec<_>>();
+    let changed = align(&rest_a, &rest_b, |i, j| {
+        a[i].changes(&b[j]).len() <= MAX_CHANGED_FIELDS
+    });
+    for (i, j) in changed {
+        (a_matched[i], b_matched[j]) = (true, true);
+        layers.push(LayerDiff::Changed {
+            from: i,
+            to: j,
+            changes: a[i].changes(&b[j]),
+        });
+    }
+
+    for i in (0..a.len()).filter(|&i| !a_matched[i]) {
+        layers.push(LayerDiff::Removed {
+            index: i,
+            layer: a[i],
+        });
+    }
+    for j in (0..b.len()).filter(|&j| !b_matched[j]) {
+        layers.push(LayerDiff::Added {
+            index: j,
+            layer: b[j],
+        });
+    }
+    layers.sort_by_key(LayerDiff::sort_key);
+
+    Diff {
+        metadata,
+        layers,
+        unchanged: unchanged.len(),
+    }
+}
+
+/// Finds the longest common subsequence of `xs` and `ys` under `matches`
+///
+/// Returns the matched pairs of elements, in order.
+fn align(
+    xs: &[usize],
+    ys: &[usize],
+    matches: impl Fn(usize, usize) -> bool,
+) -> Vec<(usize, usize)> {
+    let width = ys.len() + 1;
+    let mut lengths = vec![0usize; (xs.len() + 1) * width];
+    for x in (0..xs.len()).rev() {
+        for y in (0..ys.len()).rev() {
+            lengths[x * width + y] = if matches(xs[x], ys[y]) {
+                lengths[(x + 1) * width + y + 1] + 1
+            } else {
+                lengths[(x + 1) * width + y].max(lengths[x * width + y + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::with_capacity(lengths[0]);
+    let (mut x, mut y) = (0, 0);
+    while x < xs.len() && y < ys.len() {
+        if matches(xs[x], ys[y]) && lengths[x * width + y] == lengths[(x + 1) * width + y + 1] + 1 {
+            pairs.push((xs[x], ys[y]));
+            x += 1;
+            y += 1;
+        } else if lengths[(x + 1) * width + y] >= lengths[x * width + y + 1] {
+            x += 1;
+        } else {
+            y += 1;
+        }
+    }
+    pairs
+}
+
+/// Formats a stored color as its displayed hex value and alpha
+struct DisplayColor(RawColor);
+
+impl fmt::Display for DisplayColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let color = self.0.expand();
+        write!(f, "#{} a={}", color.to_hex(), color.a)
+    }
+}
+
+/// Formats corners as a list of coordinates
+struct DisplayVertices([Position; 4]);
+
+impl fmt::Display for DisplayVertices {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, p) in self.0.iter().enumerate() {
+            let separator = if i == 0 { "" } else { " " };
+            write!(f, "{separator}({}, {})", p.x, p.y)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Layer, parse, test::RAW_FILE};
+
+    #[test]
+    fn test_diff_identical() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let diff = diff(&sa, &sa.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, sa.layers().len());
+    }
+
+    #[test]
+    fn test_diff_sound_effect() {
+        let before = parse(Vec::from(RAW_FILE)).unwrap();
+        // The sound effect is the last byte of the header
+        let mut bytes = before.to_bytes();
+        bytes[7] = before.sound_effect() + 1;
+        let after = Payload::parse(&bytes).unwrap();
+
+        let diff = diff(&before, &after);
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.metadata,
+            vec![MetadataChange::SoundEffect {
+                before: before.sound_effect(),
+                after: before.sound_effect() + 1,
+            }]
+        );
+        assert_eq!(diff.unchanged, before.layers().len());
+    }
+
+    #[test]
+    fn test_diff_canvas() {
+        let before = parse(Vec::from(RAW_FILE)).unwrap();
+        let mut flag = before.clone();
+        flag.set_canvas(Canvas::TeamFlag);
+        assert_eq!(
+            diff(&before, &flag).metadata,
+            vec![MetadataChange::Canvas {
+                before: Some(Canvas::Standard),
+                after: Some(Canvas::TeamFlag),
+            }]
+        );
+
+        // An unknown canvas byte parses, and must not stop the comparison
+        let mut bytes = before.to_bytes();
+        bytes[5] = 0x20;
+        let unknown = Payload::parse(&bytes).unwrap();
+        let diff = diff(&before, &unknown);
+        assert_eq!(
+            diff.metadata,
+            vec![MetadataChange::Canvas {
+                before: Some(Canvas::Standard),
+                after: None,
+            }]
+        );
+        assert_eq!(diff.metadata[0].to_string(), "canvas standard -> unknown");
+    }
+
+    #[test]
+    fn test_diff_layers() {
+        let before = parse(Vec::from(RAW_FILE)).unwrap();
+        let original = before.layers();
+        let n = original.len();
+
+        // A layer differing from its neighbors in every field
+        let mut added = Layer::new(SymbolId::new(999));
+        added.set_vertices([Position { x: 1, y: 2 }; 4]);
+        added.set_raw_color(RawColor::new(3, 1, 2, 3));
+        added.set_hidden(!original[3].is_hidden());
+        let mut recolored = original[0];
+        let mut color = recolored.raw_color();
+        color.r ^= 1;
+        recolored.set_raw_color(color);
+
+        // Recolor the top layer, insert below the second, drop the fourth and
+        // move the sixth to the bottom
+        let mut after = before.clone();
+        let layers = after.layers_mut();
+        layers[0] = recolored;
+        layers.insert(2, added);
+        layers.remove(4);
+        let moved = layers.remove(5);
+        layers.push(moved);
+        after.set_name("edited");
+
+        let diff = diff(&before, &after);
+        assert_eq!(
+            diff.metadata,
+            vec![MetadataChange::Name {
+                before: before.name(),
+                after: "edited".to_string(),
+            }]
+        );
+        assert_eq!(
+            diff.layers,
+            vec![
+                LayerDiff::Changed {
+                    from: 0,
+                    to: 0,
+                    changes: vec![FieldChange::Color {
+                        before: original[0].raw_color(),
+                        after: color,
+                    }],
+                },
+                LayerDiff::Added {
+                    index: 2,
+                    layer: LayerState::of(&added),
+                },
+                LayerDiff::Removed {
+                    index: 3,
+                    layer: LayerState::of(&original[3]),
+                },
+                LayerDiff::Moved { from: 5, to: n - 1 },
+            ]
+        );
+        assert_eq!(diff.unchanged, n - 3);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::core::{
+    result::{Result, SARError},
+    sa::{Canvas, Position, Rect, SymbolArt, SymbolArtLayer},
+};
+use crate::parser::payload::{Layer, Payload};
+
+/// How the content of a standard SymbolArt is fitted into the team flag canvas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fit {
+    /// Scales the content so that all of it fits within the flag
+    Contain,
+    /// Scales the content so that it fills the flag, cropping the overflow
+    Cover,
+    /// Scales the given region of the coordinate space to fit within the flag
+    Crop(Rect),
+}
+
+/// Converts a standard SymbolArt into an alliance (team) flag
+///
+/// The content is the area covered by the visible layers within the canvas, and
+/// is scaled and centered according to `fit`. Layers that collapse to a
+/// degenerate quad once rescaled are dropped.
+///
+/// Returns [`SARError::UnsupportedCanvas`] when the canvas size is unknown and
+/// the content has to be found.
+pub fn to_team_flag(sa: &Payload, fit: Fit) -> Result<Payload> {
+    let source = match fit {
+        Fit::Crop(region) => region,
+        Fit::Contain | Fit::Cover => content(sa)?,
+    };
+    let target = Canvas::TeamFlag.view();
+
+    let source_width = (source.max.x - source.min.x).max(1) as f32;
+    let source_height = (source.max.y - source.min.y).max(1) as f32;
+    let target_width = (target.max.x - target.min.x) as f32;
+    let target_height = (target.max.y - target.min.y) as f32;
+    let scale = match fit {
+        Fit::Cover => f32::max(target_width / source_width, target_height / source_height),
+        Fit::Contain | Fit::Crop(_) => {
+            f32::min(target_width / source_width, target_height / source_height)
+        }
+    };
+
+    let layers = rescale(sa, source, target, scale);
+    let mut flag = sa.clone();
+    *flag.layers_mut() = layers;
+    flag.set_canvas(Canvas::TeamFlag);
+    Ok(flag)
+}
+
+/// Converts an alliance (team) flag into a standard SymbolArt
+///
+/// The flag canvas is scaled by `scale` and centered within the standard canvas.
+/// A scale of up to 3 keeps the whole flag visible. Layers that collapse to a
+/// degenerate quad once rescaled are dropped.
+///
+/// Returns [`SARError::UnsupportedCanvas`] when the art is not an alliance flag.
+pub fn to_standard(sa: &Payload, scale: f32) -> Result<Payload> {
+    if sa.canvas() != Some(Canvas::TeamFlag) {
+        return Err(SARError::UnsupportedCanvas(
+            "expected an alliance flag".to_string(),
+        ));
+    }
+
+    let layers = rescale(sa, Canvas::TeamFlag.view(), Canvas::Standard.view(), scale);
+    let mut standard = sa.clone();
+    *standard.layers_mut() = layers;
+    standard.set_canvas(Canvas::Standard);
+    Ok(standard)
+}
+
+/// Returns the area covered by the visible layers, limited to the visible canvas
+fn content(sa: &Payload) -> Result<Rect> {
+    let view = sa
+        .canvas()
+        .ok_or_else(|| SARError::UnsupportedCanvas("unknown canvas size".to_string()))?
+        .view();
+    let content = sa
+        .layers()
+        .iter()
+        .filter(|layer| !layer.is_hidden())
+        .map(|layer| layer.bounding_box())
+        .filter(|rect| view.intersects(rect))
+        .reduce(|a, b| Rect::enclosing(&[a.min, a.max, b.min, b.max]));
+
+    Ok(match content {
+        Some(content) => Rect::enclosing(&[
+            Position {
+                x: content.min.x.max(view.min.x),
+                y: content.min.y.max(view.min.y),
+            },
+            Position {
+                x: content.max.x.min(view.max.x),
+                y: content.max.y.min(view.max.y),
+            },
+        ]),
+        None => view,
+    })
+}
+
+/// Scales the layers so that the center of `source` lands on the center of `target`
+fn rescale(sa: &Payload, source: Rect, target: Rect, scale: f32) -> Vec<Layer> {
+    let center = |rect: Rect| {
+        (
+            (rect.min.x as f32 + rect.max.x as f32) / 2.0,
+            (rect.min.y as f32 + rect.max.y as f32) / 2.0,
+        )
+    };
+    let (source_x, source_y) = center(source);
+    let (target_x, target_y) = center(target);
+
+    sa.layers()
+        .iter()
+        .map(|layer| {
+            let (layer, _) = layer.map_vertices(|x, y| {
+                (
+                    target_x + (x - source_x) * scale,
+                    target_y + (y - source_y) * scale,
+                )
+            });
+            layer
+        })
+        .filter(|layer| layer.area() > 0.0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, test::RAW_FILE};
+
+    #[test]
+    fn test_to_team_flag() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let flag = to_team_flag(&sa, Fit::Contain).unwrap();
+
+        assert_eq!(flag.canvas(), Some(Canvas::TeamFlag));
+        assert_eq!((flag.width(), flag.height()), (32, 32));
+        assert!(flag.layers().len() <= sa.layers().len());
+        assert!(flag.layers().iter().all(|layer| layer.area() > 0.0));
+
+        let view = Canvas::TeamFlag.view();
+        assert!(
+            flag.layers()
+                .iter()
+                .filter(|layer| !layer.is_hidden())
+                .all(|layer| view.contains(&layer.bounding_box()))
+        );
+    }
+
+    #[test]
+    fn test_cover_scales_more_than_contain() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let contain = to_team_flag(&sa, Fit::Contain).unwrap();
+        let cover = to_team_flag(&sa, Fit::Cover).unwrap();
+
+        let total_area = |sa: &Payload| sa.layers().iter().map(|l| l.area()).sum::<f32>();
+        assert!(total_area(&cover) > total_area(&contain));
+    }
+
+    #[test]
+    fn test_roundtrip_canvas() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let flag = to_team_flag(&sa, Fit::Contain).unwrap();
+        let standard = to_standard(&flag, 3.0).unwrap();
+
+        assert_eq!(standard.canvas(), Some(Canvas::Standard));
+        assert!(matches!(
+            to_standard(&standard, 1.0),
+            Err(SARError::UnsupportedCanvas(_))
+        ));
+    }
+}
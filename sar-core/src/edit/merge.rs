@@ -0,0 +1,128 @@
+use crate::core::{
+    result::{Result, SARError},
+    sa::{MAX_LAYERS, Position, SymbolArt},
+};
+use crate::parser::payload::{Layer, Payload};
+
+/// Where the merged SymbolArt is stacked relative to the base
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// The merged layers are drawn on top of the base layers
+    Over,
+    /// The merged layers are drawn beneath the base layers
+    Under,
+}
+
+/// Combines two SymbolArts into one
+///
+/// The layers of `other` are moved by `offset` and stacked over or under the
+/// layers of `base`, keeping the relative order within each SymbolArt. The
+/// name, author, canvas and sound effect are taken from `base`.
+///
+/// Returns [`SARError::LayerLimitExceeded`] when the combined layer count is
+/// over [`MAX_LAYERS`], and [`SARError::PositionOutOfRange`] when the offset
+/// moves a vertex outside of the coordinate space.
+pub fn merge(
+    base: &Payload,
+    other: &Payload,
+    offset: (i32, i32),
+    placement: Placement,
+) -> Result<Payload> {
+    let base_layers = base.layers();
+    let other_layers = other.layers();
+
+    let count = base_layers.len() + other_layers.len();
+    if count > MAX_LAYERS {
+        return Err(SARError::LayerLimitExceeded {
+            count,
+            limit: MAX_LAYERS,
+        });
+    }
+
+    let other_layers = other_layers
+        .iter()
+        .map(|layer| translate(layer, offset))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Layers are ordered from top to bottom
+    let layers = match placement {
+        Placement::Over => [other_layers, base_layers].concat(),
+        Placement::Under => [base_layers, other_layers].concat(),
+    };
+
+    let mut merged = base.clone();
+    *merged.layers_mut() = layers;
+    Ok(merged)
+}
+
+/// Moves every vertex of the layer by the offset
+fn translate(layer: &Layer, (dx, dy): (i32, i32)) -> Result<Layer> {
+    let mut vertices = layer.vertices();
+    for vertex in vertices.iter_mut() {
+        let x = vertex.x as i32 + dx;
+        let y = vertex.y as i32 + dy;
+        *vertex = match (u8::try_from(x), u8::try_from(y)) {
+            (Ok(x), Ok(y)) => Position { x, y },
+            _ => return Err(SARError::PositionOutOfRange(x, y)),
+        };
+    }
+
+    let mut layer = *layer;
+    layer.set_vertices(vertices);
+    Ok(layer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::sa::SymbolArtLayer,
+        parse,
+        test::{RAW_FILE, RAW_FILE_UNCOMPRESSED},
+    };
+
+    #[test]
+    fn test_merge_stacking_order() {
+        let base = parse(Vec::from(RAW_FILE_UNCOMPRESSED)).unwrap();
+        let other = parse(Vec::from(RAW_FILE_UNCOMPRESSED)).unwrap();
+        let len = base.layers().len();
+
+        let merged = merge(&base, &other, (0, 0), Placement::Under).unwrap();
+        let layers = merged.layers();
+        assert_eq!(layers.len(), len * 2);
+        assert_eq!(layers[..len], base.layers()[..]);
+        assert_eq!(layers[len..], other.layers()[..]);
+    }
+
+    #[test]
+    fn test_merge_offset() {
+        let base = parse(Vec::from(RAW_FILE_UNCOMPRESSED)).unwrap();
+        let other = parse(Vec::from(RAW_FILE_UNCOMPRESSED)).unwrap();
+
+        let merged = merge(&base, &other, (-1, 1), Placement::Over).unwrap();
+        let moved = &merged.layers()[0];
+        let original = &other.layers()[0];
+        assert_eq!(moved.top_left().x + 1, original.top_left().x);
+        assert_eq!(moved.top_left().y, original.top_left().y + 1);
+
+        let result = merge(&base, &other, (256, 0), Placement::Over);
+        assert!(matches!(result, Err(SARError::PositionOutOfRange(_, _))));
+    }
+
+    #[test]
+    fn test_merge_layer_limit() {
+        let base = parse(Vec::from(RAW_FILE)).unwrap();
+        let other = parse(Vec::from(RAW_FILE)).unwrap();
+        let base = merge(&base, &other, (0, 0), Placement::Over).unwrap();
+        let count = base.layers().len() + other.layers().len();
+
+        let result = merge(&base, &other, (0, 0), Placement::Over);
+        match result {
+            Err(SARError::LayerLimitExceeded { count: c, limit }) => {
+                assert_eq!(c, count);
+                assert_eq!(limit, MAX_LAYERS);
+            }
+            _ => panic!("expected LayerLimitExceeded"),
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Editing operations over parsed SymbolArts
+//!
+//! Every operation works on a [`Payload`](crate::Payload) so the result can be
+//! rendered with [`SymbolArtDrawer`](crate::SymbolArtDrawer) or written back with
+//! [`encode`](crate::encode).
+
+mod flag;
+mod merge;
+mod optimize;
+mod recolor;
+mod reduce;
+mod substitute;
+
+pub use flag::{Fit, to_standard, to_team_flag};
+pub use merge::{Placement, merge};
+pub use optimize::{Optimization, Removal, optimize};
+pub use recolor::{Recolor, recolor};
+pub use reduce::{Reduction, reduce_palette};
+pub use substitute::substitute;
@@ -0,0 +1,205 @@
+use std::fmt;
+
+use crate::Result;
+use crate::analysis::visibility;
+use crate::core::sa::{SymbolArt, SymbolArtLayer};
+use crate::parser::payload::Payload;
+use crate::renderer::{SymbolArtDrawer, draw::Drawer};
+
+/// Why the optimizer removed a layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Removal {
+    /// The layer is hidden
+    Hidden,
+    /// The layer is fully transparent
+    Transparent,
+    /// The quad has no area
+    Degenerate,
+    /// The quad lies entirely outside the visible canvas
+    OffCanvas,
+    /// Every pixel of the layer is under fully opaque layers above it
+    Occluded,
+}
+
+impl fmt::Display for Removal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Removal::Hidden => "hidden",
+            Removal::Transparent => "transparent",
+            Removal::Degenerate => "degenerate",
+            Removal::OffCanvas => "off-canvas",
+            Removal::Occluded => "occluded",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+/// The outcome of an optimization
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Optimization {
+    /// The removed layers, by their index in the original art counted from the top
+    pub removed: Vec<(usize, Removal)>,
+}
+
+impl Optimization {
+    /// Returns the number of layers saved
+    pub fn layers_saved(&self) -> usize {
+        self.removed.len()
+    }
+}
+
+/// Removes the layers that do not contribute to the rendered image
+///
+/// Fully transparent, degenerate and off-canvas layers are removed, as are
+/// layers whose every pixel is covered by fully opaque layers above them.
+/// Hidden layers are only removed when `remove_hidden` is set, since they may
+/// be kept on purpose for later editing.
+///
+/// The art is rendered with `drawer` before and after, and a removal that would
+/// change a single pixel is undone, so the result always renders identically.
+/// The drawer rounds every chunk of layers it composites in parallel, so removing
+/// a layer shifts the rounding of the chunks below it; a drawer whose chunk size
+/// covers every layer composites in layer order and lets more layers go.
+pub fn optimize(
+    sa: &mut Payload,
+    drawer: &SymbolArtDrawer,
+    remove_hidden: bool,
+) -> Result<Optimization> {
+    let candidates = candidates(sa, drawer, remove_hidden)?;
+    if candidates.is_empty() {
+        return Ok(Optimization::default());
+    }
+
+    let expected = drawer.draw(sa)?;
+    let renders_same = |removed: &[(usize, Removal)]| -> Result<bool> {
+        Ok(drawer.draw(&without(sa, removed))? == expected)
+    };
+
+    // Compositing order can round differently once layers are gone, so the
+    // removals are retried one at a time when removing them all at once differs
+    let removed = if renders_same(&candidates)? {
+        candidates
+    } else {
+        let mut removed = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            removed.push(candidate);
+            if !renders_same(&removed)? {
+                removed.pop();
+            }
+        }
+        removed
+    };
+
+    *sa = without(sa, &removed);
+    Ok(Optimization { removed })
+}
+
+/// Finds the layers that should not contribute to the rendered image
+fn candidates(
+    sa: &Payload,
+    drawer: &SymbolArtDrawer,
+    remove_hidden: bool,
+) -> Result<Vec<(usize, Removal)>> {
+    let view = sa.canvas().map(|canvas| canvas.view());
+    let visibility = visibility(drawer, sa)?;
+
+    let mut candidates = Vec::new();
+    for (i, (layer, visibility)) in sa.layers().iter().zip(&visibility.layers).enumerate() {
+        let removal = if layer.is_hidden() {
+            remove_hidden.then_some(Removal::Hidden)
+        } else if layer.raw_color().a == 0 {
+            Some(Removal::Transparent)
+        } else if layer.area() == 0.0 {
+            Some(Removal::Degenerate)
+        } else if view.is_some_and(|view| !view.intersects(&layer.bounding_box())) {
+            Some(Removal::OffCanvas)
+        } else if visibility.is_some_and(|visibility| visibility.visible == 0) {
+            Some(Removal::Occluded)
+        } else {
+            None
+        };
+        if let Some(removal) = removal {
+            candidates.push((i, removal));
+        }
+    }
+    Ok(candidates)
+}
+
+/// Returns a copy of the art without the given layers
+fn without(sa: &Payload, removed: &[(usize, Removal)]) -> Payload {
+    let mut result = sa.clone();
+    let mut index = 0;
+    result.layers_mut().retain(|_| {
+        let keep = !removed.iter().any(|(i, _)| *i == index);
+        index += 1;
+        keep
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Layer, SymbolId,
+        core::sa::Position,
+        parse,
+        test::{RAW_FILE, RAW_FILE_UNCOMPRESSED},
+    };
+
+    #[test]
+    fn test_optimize_renders_identically() {
+        let drawer = SymbolArtDrawer::new();
+        for raw in [RAW_FILE, RAW_FILE_UNCOMPRESSED] {
+            let mut sa = parse(Vec::from(raw)).unwrap();
+            let before = drawer.draw(&sa).unwrap();
+            let layers = sa.layers().len();
+
+            let optimization = optimize(&mut sa, &drawer, false).unwrap();
+            assert_eq!(sa.layers().len(), layers - optimization.layers_saved());
+            assert_eq!(drawer.draw(&sa).unwrap(), before);
+        }
+    }
+
+    #[test]
+    fn test_optimize_removals() {
+        let drawer = SymbolArtDrawer::new();
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let p = |x, y| Position { x, y };
+
+        // A small opaque disc inside a large one, plus layers that never show
+        let mut cover = Layer::new(SymbolId::new(731));
+        cover.set_vertices([p(96, 96), p(96, 160), p(160, 96), p(160, 160)]);
+        let mut covered = cover;
+        covered.set_vertices([p(120, 120), p(120, 136), p(136, 120), p(136, 136)]);
+        let mut transparent = cover;
+        let mut color = transparent.raw_color();
+        color.a = 0;
+        transparent.set_raw_color(color);
+        let mut degenerate = cover;
+        degenerate.set_vertices([p(120, 120); 4]);
+        let mut off_canvas = cover;
+        off_canvas.set_vertices([p(0, 0), p(0, 10), p(10, 0), p(10, 10)]);
+        let mut hidden = covered;
+        hidden.set_hidden(true);
+
+        let layers = sa.layers_mut();
+        layers.clear();
+        layers.extend([cover, covered, transparent, degenerate, off_canvas, hidden]);
+
+        let optimization = optimize(&mut sa.clone(), &drawer, false).unwrap();
+        assert_eq!(
+            optimization.removed,
+            vec![
+                (1, Removal::Occluded),
+                (2, Removal::Transparent),
+                (3, Removal::Degenerate),
+                (4, Removal::OffCanvas),
+            ]
+        );
+
+        let optimization = optimize(&mut sa, &drawer, true).unwrap();
+        assert_eq!(optimization.removed.last(), Some(&(5, Removal::Hidden)));
+        assert_eq!(sa.layers(), &[cover]);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::core::{
+    color::ColorSpace,
+    sa::{Color, SymbolArtLayer},
+};
+use crate::parser::payload::Payload;
+use crate::query::Predicate;
+
+/// A color transformation applied to every selected layer
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recolor {
+    /// Replaces colors found in the table, leaving other colors untouched
+    ///
+    /// Colors are compared after quantization, so `ff0000` matches a layer whose
+    /// color is stored as pure red. Alpha is kept as is.
+    Map(Vec<(Color, Color)>),
+    /// Shifts hue by degrees, and saturation and lightness by an amount between -1 and 1
+    Shift {
+        hue: f32,
+        saturation: f32,
+        lightness: f32,
+    },
+    /// Blends every color toward `color` by `amount`, between 0 and 1
+    Tint { color: Color, amount: f32 },
+    /// Replaces every color with the closest one in the palette, measured in `space`
+    Palette {
+        colors: Vec<Color>,
+        space: ColorSpace,
+    },
+}
+
+impl Recolor {
+    /// Returns the new color for `color`, or `None` when it is left untouched
+    fn apply(&self, color: Color) -> Option<Color> {
+        match self {
+            Recolor::Map(table) => table
+                .iter()
+                .find(|(from, _)| same_rgb(&from.quantize().color.expand(), &color))
+                .map(|(_, to)| Color::new(color.a, to.r, to.g, to.b)),
+            Recolor::Shift {
+                hue,
+                saturation,
+                lightness,
+            } => {
+                let [h, s, l] = color.to_hsl();
+                Some(Color::from_hsl(
+                    color.a,
+                    h + hue,
+                    s + saturation,
+                    l + lightness,
+                ))
+            }
+            Recolor::Tint {
+                color: target,
+                amount,
+            } => {
+                let amount = amount.clamp(0.0, 1.0);
+                let mix = |from: u8, to: u8| {
+                    (from as f32 + (to as f32 - from as f32) * amount).round() as u8
+                };
+                Some(Color::new(
+                    color.a,
+                    mix(color.r, target.r),
+                    mix(color.g, target.g),
+                    mix(color.b, target.b),
+                ))
+            }
+            Recolor::Palette { colors, space } => colors
+                .iter()
+                .min_by(|a, b| {
+                    color
+                        .distance_in(a, *space)
+                        .total_cmp(&color.distance_in(b, *space))
+                })
+                .map(|to| Color::new(color.a, to.r, to.g, to.b)),
+        }
+    }
+}
+
+/// Recolors the layers matching `selection`, or every layer when it is `None`
+///
+/// New colors are quantized to the nearest color a SAR file can store. Returns
+/// the number of layers whose stored color changed.
+pub fn recolor(sa: &mut Payload, recolor: &Recolor, selection: Option<&Predicate>) -> usize {
+    let mut changed = 0;
+    for (i, layer) in sa.layers_mut().iter_mut().enumerate() {
+        if selection.is_some_and(|selection| !selection.matches(i, &*layer)) {
+            continue;
+        }
+        let Some(color) = recolor.apply(layer.color()) else {
+            continue;
+        };
+
+        let mut raw = color.quantize().color;
+        // Alpha is never changed by recoloring
+        raw.a = layer.raw_color().a;
+        if raw != layer.raw_color() {
+            layer.set_raw_color(raw);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+fn same_rgb(a: &Color, b: &Color) -> bool {
+    (a.r, a.g, a.b) == (b.r, b.g, b.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::sa::SymbolArt, parse, test::RAW_FILE};
+
+    #[test]
+    fn test_recolor_map() {
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let from = sa.layers()[0].color();
+        // Every channel at the far end from `from`, and exact after quantization
+        let far = |value: u8| if value < 128 { 255 } else { 0 };
+        let to = Color::new(255, far(from.r), far(from.g), far(from.b));
+        let expected = sa
+            .layers()
+            .iter()
+            .filter(|layer| same_rgb(&layer.color(), &from))
+            .count();
+
+        let changed = recolor(&mut sa, &Recolor::Map(vec![(from, to)]), None);
+        assert!(expected > 0);
+        assert_eq!(changed, expected);
+        assert!(same_rgb(&sa.layers()[0].color(), &to));
+        assert_eq!(sa.layers()[0].color().a, from.a);
+    }
+
+    #[test]
+    fn test_recolor_selection() {
+        let original = parse(Vec::from(RAW_FILE)).unwrap();
+        let mut sa = original.clone();
+        let palette = Recolor::Palette {
+            colors: vec![Color::new(255, 0, 0, 0)],
+            space: ColorSpace::Lab,
+        };
+
+        recolor(&mut sa, &palette, Some(&Predicate::index_range(0..10)));
+        for (i, (layer, before)) in sa.layers().iter().zip(original.layers()).enumerate() {
+            if i < 10 {
+                assert!(same_rgb(&layer.color(), &Color::new(255, 0, 0, 0)));
+            } else {
+                assert_eq!(layer.raw_color(), before.raw_color());
+            }
+        }
+    }
+
+    #[test]
+    fn test_recolor_shift_and_tint() {
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let shift = Recolor::Shift {
+            hue: 0.0,
+            saturation: 0.0,
+            lightness: 0.0,
+        };
+        assert_eq!(recolor(&mut sa, &shift, None), 0);
+
+        let white = Color::new(255, 255, 255, 255);
+        let tint = Recolor::Tint {
+            color: white,
+            amount: 1.0,
+        };
+        recolor(&mut sa, &tint, None);
+        assert!(
+            sa.layers()
+                .iter()
+                .all(|layer| same_rgb(&layer.color(), &white))
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::Result;
+use crate::analysis::cluster;
+use crate::core::{
+    color::ColorSpace,
+    sa::{Color, SymbolArt, SymbolArtLayer},
+};
+use crate::parser::payload::Payload;
+use crate::renderer::SymbolArtDrawer;
+
+/// The outcome of a palette reduction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reduction {
+    /// The representative colors the layers were mapped to, most covering first
+    pub colors: Vec<Color>,
+    /// The number of layers whose stored color changed
+    pub layers_changed: usize,
+    /// The coverage-weighted mean CIELAB distance between old and new layer colors
+    pub mean_error: f32,
+    /// The largest CIELAB distance between an old and a new layer color
+    pub max_error: f32,
+}
+
+/// Reduces the layer colors to at most `colors` representatives
+///
+/// Layer colors are clustered in CIELAB, each weighted by the pixels its layers
+/// cover in the rendered image, so large areas keep their color while small
+/// details are pulled toward them. Layers that cover nothing still count as a
+/// single pixel. Every layer is then recolored to the quantized representative of
+/// its cluster, keeping its alpha.
+pub fn reduce_palette(
+    sa: &mut Payload,
+    drawer: &SymbolArtDrawer,
+    colors: usize,
+) -> Result<Reduction> {
+    let coverage = drawer.coverage(sa)?;
+
+    // Distinct colors, ignoring alpha, with the summed coverage of their layers
+    let mut distinct: Vec<(Color, f32)> = Vec::new();
+    let mut members = Vec::with_capacity(coverage.len());
+    for (layer, pixels) in sa.layers().iter().zip(&coverage) {
+        let color = opaque(layer.color());
+        let weight = (*pixels).max(1) as f32;
+        match distinct.iter().position(|(c, _)| *c == color) {
+            Some(i) => {
+                distinct[i].1 += weight;
+                members.push(i);
+            }
+            None => {
+                distinct.push((color, weight));
+                members.push(distinct.len() - 1);
+            }
+        }
+    }
+
+    let clusters = cluster(&distinct, colors, ColorSpace::Lab);
+    let mut representatives = vec![Color::new(255, 0, 0, 0); distinct.len()];
+    let mut weights = Vec::with_capacity(clusters.len());
+    for cluster in &clusters {
+        let representative = cluster.color.quantize_in(ColorSpace::Lab).color.expand();
+        for member in &cluster.members {
+            representatives[*member] = representative;
+        }
+        let weight = cluster.members.iter().map(|i| distinct[*i].1).sum::<f32>();
+        weights.push((representative, weight));
+    }
+    weights.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut layers_changed = 0;
+    let mut max_error: f32 = 0.0;
+    let mut weighted_error = 0.0;
+    let mut total_pixels = 0;
+    for ((layer, pixels), member) in sa.layers_mut().iter_mut().zip(&coverage).zip(members) {
+        let representative = representatives[member];
+        let error = layer.color().distance_in(&representative, ColorSpace::Lab);
+        max_error = max_error.max(error);
+        weighted_error += error * *pixels as f32;
+        total_pixels += pixels;
+
+        let mut raw = representative.quantize().color;
+        raw.a = layer.raw_color().a;
+        if raw != layer.raw_color() {
+            layer.set_raw_color(raw);
+            layers_changed += 1;
+        }
+    }
+
+    Ok(Reduction {
+        colors: weights.into_iter().map(|(color, _)| color).collect(),
+        layers_changed,
+        mean_error: if total_pixels > 0 {
+            weighted_error / total_pixels as f32
+        } else {
+            0.0
+        },
+        max_error,
+    })
+}
+
+fn opaque(color: Color) -> Color {
+    Color::new(255, color.r, color.g, color.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, test::RAW_FILE};
+
+    #[test]
+    fn test_reduce_palette() {
+        let drawer = SymbolArtDrawer::new();
+        let original = parse(Vec::from(RAW_FILE)).unwrap();
+
+        let mut sa = original.clone();
+        let reduction = reduce_palette(&mut sa, &drawer, 3).unwrap();
+        assert!(reduction.colors.len() <= 3);
+        assert!(reduction.layers_changed > 0);
+        assert!(reduction.mean_error <= reduction.max_error);
+
+        let mut remaining = sa
+            .layers()
+            .iter()
+            .map(|layer| opaque(layer.color()))
+            .collect::<Vec<_>>();
+        remaining.sort_by_key(|color| color.to_hex());
+        remaining.dedup();
+        assert!(remaining.len() <= 3);
+        for (layer, before) in sa.layers().iter().zip(original.layers()) {
+            assert_eq!(layer.raw_color().a, before.raw_color().a);
+        }
+    }
+
+    #[test]
+    fn test_reduce_palette_keeps_small_palettes() {
+        let drawer = SymbolArtDrawer::new();
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+
+        let reduction = reduce_palette(&mut sa, &drawer, 64).unwrap();
+        assert_eq!(reduction.layers_changed, 0);
+        assert_eq!(reduction.max_error, 0.0);
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use crate::Result;
+use crate::core::{result::SARError, sa::SymbolArtLayer, symbol::SymbolId};
+use crate::parser::payload::Payload;
+use crate::query::Predicate;
+use crate::renderer::resource::Resource;
+
+/// Replaces the symbols of the layers matching `selection`, or of every layer when it is `None`
+///
+/// Every layer whose symbol is a key of `map` gets the mapped symbol instead.
+/// Fails with [`SARError::SymbolNotFound`] before changing anything when a target
+/// symbol cannot be drawn. Returns the number of layers whose symbol changed.
+pub fn substitute(
+    sa: &mut Payload,
+    map: &HashMap<SymbolId, SymbolId>,
+    selection: Option<&Predicate>,
+) -> Result<usize> {
+    if let Some(target) = map.values().find(|target| !Resource::contains(**target)) {
+        return Err(SARError::SymbolNotFound(*target));
+    }
+
+    let mut changed = 0;
+    for (i, layer) in sa.layers_mut().iter_mut().enumerate() {
+        if selection.is_some_and(|selection| !selection.matches(i, &*layer)) {
+            continue;
+        }
+        let Some(target) = map.get(&layer.symbol().id()) else {
+            continue;
+        };
+        if *target != layer.symbol().id() {
+            layer.set_symbol(*target);
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::sa::SymbolArt, parse, test::RAW_FILE};
+
+    #[test]
+    fn test_substitute() {
+        let original = parse(Vec::from(RAW_FILE)).unwrap();
+        let mut sa = original.clone();
+        let from = original.layers()[0].symbol().id();
+        let to = SymbolId::new(if from.id() == 1 { 2 } else { 1 });
+        let expected = original
+            .layers()
+            .iter()
+            .filter(|layer| layer.symbol().id() == from)
+            .count();
+
+        let changed = substitute(&mut sa, &HashMap::from([(from, to)]), None).unwrap();
+        assert_eq!(changed, expected);
+        for (layer, before) in sa.layers().iter().zip(original.layers()) {
+            if before.symbol().id() == from {
+                assert_eq!(layer.symbol().id(), to);
+            } else {
+                assert_eq!(layer.symbol().id(), before.symbol().id());
+            }
+        }
+    }
+
+    #[test]
+    fn test_substitute_selection_and_validation() {
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let from = sa.layers()[0].symbol().id();
+
+        let invalid = HashMap::from([(from, SymbolId::new(100))]);
+        assert!(matches!(
+            substitute(&mut sa, &invalid, None),
+            Err(SARError::SymbolNotFound(_))
+        ));
+
+        let map = HashMap::from([(from, SymbolId::new(721))]);
+        let selection = Predicate::index_range(1..sa.layers().len());
+        substitute(&mut sa, &map, Some(&selection)).unwrap();
+        assert_eq!(sa.layers()[0].symbol().id(), from);
+    }
+}
@@ -0,0 +1,166 @@
+//! Content fingerprints and PSO2 cache-style file names
+//!
+//! The game caches SymbolArts as `sa<32 hex digits>.sar`, where the digits are
+//! the MD5 digest of the file with its body decrypted but still compressed.
+//! That [`cache_digest`] identifies a file byte for byte, so the same art saved
+//! with another compression mode gets another name. [`content_hash`] instead
+//! hashes the canonical uncompressed body and is stable across re-encoding.
+//!
+//! ```
+//! use sar_core::fingerprint::{cache_name, verify_name};
+//!
+//! let bytes = include_bytes!("../../fixture/sa0a1d081b8a108bb8c9847c4cd83db662.sar");
+//! assert_eq!(
+//!     cache_name(bytes).unwrap(),
+//!     "sa0a1d081b8a108bb8c9847c4cd83db662.sar"
+//! );
+//! assert!(verify_name("sa0a1d081b8a108bb8c9847c4cd83db662.sar", bytes).unwrap());
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use md5::{Digest as _, Md5};
+
+use crate::core::result::{Result, SARError};
+use crate::parser::{decode, payload::Payload};
+
+const HEADER_SIZE: usize = 4;
+
+/// A 128-bit digest, formatted as 32 lowercase hex digits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Digest([u8; 16]);
+
+impl Digest {
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    fn of(data: &[u8]) -> Self {
+        Self(Md5::digest(data).into())
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Digest {
+    type Err = SARError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || SARError::InvalidDigest(s.to_string());
+        if s.len() != 32 || !s.is_ascii() {
+            return Err(invalid());
+        }
+        let mut digest = [0; 16];
+        for (i, byte) in digest.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(digest))
+    }
+}
+
+/// Computes the digest the game uses to name the cached copy of a SAR file
+pub fn cache_digest(bytes: &[u8]) -> Result<Digest> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(SARError::InvalidFileHeader);
+    }
+    decode::validate_format(bytes)?;
+
+    let mut decrypted = bytes.to_vec();
+    decode::decrypt(&mut decrypted[HEADER_SIZE..]);
+    Ok(Digest::of(&decrypted))
+}
+
+/// Computes the cache-style file name of a SAR file, e.g. `sa<digest>.sar`
+pub fn cache_name(bytes: &[u8]) -> Result<String> {
+    Ok(format_name(&cache_digest(bytes)?))
+}
+
+/// Computes a hash of the parsed art that ignores how the file was encoded
+///
+/// Two files have the same content hash when they hold the same header, layers
+/// and name, whatever their compression mode.
+pub fn content_hash(sa: &Payload) -> Digest {
+    Digest::of(&sa.to_bytes())
+}
+
+/// Formats a digest as a cache-style file name
+pub fn format_name(digest: &Digest) -> String {
+    format!("sa{digest}.sar")
+}
+
+/// Extracts the digest from a cache-style file name
+///
+/// Returns `None` when the name does not follow the `sa<32 hex digits>.sar` pattern.
+pub fn parse_name(file_name: &str) -> Option<Digest> {
+    file_name
+        .strip_prefix("sa")?
+        .strip_suffix(".sar")?
+        .parse()
+        .ok()
+}
+
+/// Checks whether a cache-style file name matches the content of the file
+///
+/// Names that do not follow the cache pattern never match.
+pub fn verify_name(file_name: &str, bytes: &[u8]) -> Result<bool> {
+    match parse_name(file_name) {
+        Some(digest) => Ok(cache_digest(bytes)? == digest),
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Compression, encode, parse,
+        test::{RAW_FILE, RAW_FILE_UNCOMPRESSED},
+    };
+
+    #[test]
+    fn test_cache_name_matches_fixtures() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixture");
+        for entry in std::fs::read_dir(dir).unwrap().take(20) {
+            let path = entry.unwrap().path();
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            let bytes = std::fs::read(&path).unwrap();
+            assert!(verify_name(file_name, &bytes).unwrap(), "{file_name}");
+        }
+    }
+
+    #[test]
+    fn test_content_hash_ignores_compression() {
+        for raw in [RAW_FILE, RAW_FILE_UNCOMPRESSED] {
+            let sa = parse(Vec::from(raw)).unwrap();
+            for compression in [Compression::None, Compression::Compressed] {
+                let bytes = encode(&sa, compression).unwrap();
+                let reencoded = parse(bytes.clone()).unwrap();
+                assert_eq!(content_hash(&reencoded), content_hash(&sa));
+                assert_eq!(
+                    cache_digest(&bytes).unwrap() == cache_digest(raw).unwrap(),
+                    bytes == raw
+                );
+            }
+        }
+
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let other = parse(Vec::from(RAW_FILE_UNCOMPRESSED)).unwrap();
+        assert_ne!(content_hash(&sa), content_hash(&other));
+    }
+
+    #[test]
+    fn test_parse_name() {
+        let digest = cache_digest(RAW_FILE).unwrap();
+        assert_eq!(parse_name(&format_name(&digest)), Some(digest));
+        assert_eq!(parse_name("sa0a1d.sar"), None);
+        assert_eq!(parse_name("art.sar"), None);
+        assert!(!verify_name("art.sar", RAW_FILE).unwrap());
+    }
+}
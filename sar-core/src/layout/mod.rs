@@ -0,0 +1,155 @@
+//! Multi-part SymbolArt layouts
+//!
+//! Large designs are often posted as several SymbolArts placed side by side,
+//! because a single file cannot hold enough layers. A [`Layout`] is the manifest
+//! describing which part goes where in the grid. Whole layouts are rendered with
+//! [`SymbolArtDrawer::draw_layout`](crate::SymbolArtDrawer::draw_layout), and
+//! [`split`] divides an over-budget SymbolArt into a layout of valid parts.
+//!
+//! The manifest is a plain text file:
+//!
+//! ```text
+//! grid 2 1
+//! 0 0 design-0-0.sar
+//! 1 0 design-1-0.sar
+//! ```
+//!
+//! The first line holds the number of columns and rows, and each following line
+//! holds the column, row and file name of a part.
+
+mod split;
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::core::result::{Result, SARError};
+
+pub use split::{Split, split, split_auto};
+
+/// A single SymbolArt within a layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutPart {
+    /// Zero-based column, counted from the left
+    pub column: u32,
+    /// Zero-based row, counted from the top
+    pub row: u32,
+    /// File name of the part, relative to the manifest
+    pub file: String,
+}
+
+/// A manifest placing several SymbolArts in a grid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    columns: u32,
+    rows: u32,
+    parts: Vec<LayoutPart>,
+}
+
+impl Layout {
+    pub fn new(columns: u32, rows: u32) -> Self {
+        Self {
+            columns,
+            rows,
+            parts: Vec::new(),
+        }
+    }
+
+    /// Places a part in the grid
+    ///
+    /// Returns [`SARError::InvalidLayout`] when the cell is outside of the grid.
+    pub fn with_part(mut self, column: u32, row: u32, file: impl Into<String>) -> Result<Self> {
+        if column >= self.columns || row >= self.rows {
+            return Err(SARError::InvalidLayout(format!(
+                "cell {column},{row} is outside of the {}x{} grid",
+                self.columns, self.rows
+            )));
+        }
+        self.parts.push(LayoutPart {
+            column,
+            row,
+            file: file.into(),
+        });
+        Ok(self)
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn parts(&self) -> &[LayoutPart] {
+        &self.parts
+    }
+}
+
+impl Display for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "grid {} {}", self.columns, self.rows)?;
+        for part in &self.parts {
+            writeln!(f, "{} {} {}", part.column, part.row, part.file)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Layout {
+    type Err = SARError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = |line: &str| SARError::InvalidLayout(format!("invalid line: {line}"));
+
+        let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+        let grid = lines
+            .next()
+            .ok_or_else(|| SARError::InvalidLayout("empty manifest".to_string()))?;
+        let (columns, rows) = match grid.split_whitespace().collect::<Vec<_>>()[..] {
+            ["grid", columns, rows] => (
+                columns.parse().map_err(|_| invalid(grid))?,
+                rows.parse().map_err(|_| invalid(grid))?,
+            ),
+            _ => return Err(invalid(grid)),
+        };
+
+        let mut layout = Layout::new(columns, rows);
+        for line in lines {
+            let mut fields = line.splitn(3, char::is_whitespace);
+            let (Some(column), Some(row), Some(file)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid(line));
+            };
+            let column = column.parse().map_err(|_| invalid(line))?;
+            let row = row.parse().map_err(|_| invalid(line))?;
+            layout = layout.with_part(column, row, file.trim())?;
+        }
+
+        Ok(layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let layout = Layout::new(2, 1)
+            .with_part(0, 0, "left.sar")
+            .unwrap()
+            .with_part(1, 0, "right part.sar")
+            .unwrap();
+
+        let manifest = layout.to_string();
+        assert_eq!(manifest, "grid 2 1\n0 0 left.sar\n1 0 right part.sar\n");
+        assert_eq!(manifest.parse::<Layout>().unwrap(), layout);
+    }
+
+    #[test]
+    fn test_manifest_invalid() {
+        assert!("".parse::<Layout>().is_err());
+        assert!("grid 2".parse::<Layout>().is_err());
+        assert!("grid 1 1\n1 0 out.sar".parse::<Layout>().is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use crate::core::{
+    result::{Result, SARError},
+    sa::{CANVAS_SIZE, MAX_LAYERS, Position, SymbolArt},
+};
+use crate::parser::payload::Payload;
+
+use super::Layout;
+
+/// Grids tried by [`split_auto`], from the fewest parts to the most
+const AUTO_GRIDS: [(u32, u32); 4] = [(1, 1), (2, 1), (1, 2), (2, 2)];
+
+/// The result of splitting a SymbolArt into a multi-part layout
+#[derive(Debug, Clone)]
+pub struct Split {
+    /// The manifest placing the parts, in the same order as `parts`
+    pub layout: Layout,
+    /// The parts, each within the layer budget
+    pub parts: Vec<Payload>,
+    /// The scale the design is drawn at across the grid
+    pub scale: u32,
+    /// The number of layer copies left out of a cell they reach into, because
+    /// they do not fit its coordinate space
+    pub clipped_layers: usize,
+}
+
+/// Splits a SymbolArt into a `columns` x `rows` grid of parts
+///
+/// The design is scaled by the same whole factor on both axes, the largest that
+/// fits the grid, and centered on it, so the parts rendered at scale 1 and
+/// placed side by side reproduce the original rendered at [`Split::scale`].
+/// Non-square grids leave a transparent margin around the design.
+///
+/// A layer is copied into every cell it reaches into, and layers outside of the
+/// visible area are dropped. A copy whose quad does not fit the coordinate space
+/// of its cell is left out rather than distorted, and counted in
+/// [`Split::clipped_layers`]; the part of that layer within the cell is then
+/// missing from the layout. Part files are named `{name}-{column}-{row}.sar` in
+/// the manifest.
+///
+/// Returns [`SARError::LayerLimitExceeded`] when a part is still over
+/// [`MAX_LAYERS`].
+pub fn split(sa: &Payload, columns: u32, rows: u32, name: &str) -> Result<Split> {
+    if columns == 0 || rows == 0 {
+        return Err(SARError::InvalidLayout(format!(
+            "grid must have at least one cell: {columns}x{rows}"
+        )));
+    }
+
+    let canvas = sa
+        .canvas()
+        .ok_or_else(|| SARError::UnsupportedCanvas("unknown canvas size".to_string()))?;
+    let view = canvas.view();
+    let (width, height) = (canvas.width() as i32, canvas.height() as i32);
+    let scale = columns.min(rows);
+    let s = scale as i32;
+
+    // Where the drawer crops the view of the original at this scale, and where
+    // the scaled view lands in the grid, so that whole pixels line up
+    let crop = |view_size: i32| {
+        let canvas_size = (CANVAS_SIZE as f32 * scale as f32) as i32;
+        canvas_size / 2 - (view_size as f32 * scale as f32) as i32 / 2
+    };
+    let origin = (
+        crop(width) - (width * columns as i32 - width * s) / 2,
+        crop(height) - (height * rows as i32 - height * s) / 2,
+    );
+
+    let mut layout = Layout::new(columns, rows);
+    let mut parts = Vec::new();
+    let mut clipped_layers = 0;
+    for row in 0..rows as i32 {
+        for column in 0..columns as i32 {
+            // Maps a point of the original canvas onto the canvas of this cell
+            let offset = (
+                view.min.x as i32 - width * column - origin.0,
+                view.min.y as i32 - height * row - origin.1,
+            );
+            let map = |position: Position| {
+                (
+                    position.x as i32 * s + offset.0,
+                    position.y as i32 * s + offset.1,
+                )
+            };
+
+            let mut layers = Vec::new();
+            for layer in sa.layers() {
+                let vertices = layer.vertices().map(map);
+                let (min_x, max_x, min_y, max_y) = vertices.iter().fold(
+                    (i32::MAX, i32::MIN, i32::MAX, i32::MIN),
+                    |(min_x, max_x, min_y, max_y), (x, y)| {
+                        (min_x.min(*x), max_x.max(*x), min_y.min(*y), max_y.max(*y))
+                    },
+                );
+                let touches = min_x <= view.max.x as i32
+                    && max_x >= view.min.x as i32
+                    && min_y <= view.max.y as i32
+                    && max_y >= view.min.y as i32;
+                if !touches {
+                    continue;
+                }
+
+                let fits = vertices.iter().all(|(x, y)| {
+                    (0..CANVAS_SIZE as i32).contains(x) && (0..CANVAS_SIZE as i32).contains(y)
+                });
+                if !fits {
+                    clipped_layers += 1;
+                    continue;
+                }
+
+                let mut layer = layer;
+                layer.set_vertices(vertices.map(|(x, y)| Position {
+                    x: x as u8,
+                    y: y as u8,
+                }));
+                layers.push(layer);
+            }
+
+            if layers.len() > MAX_LAYERS {
+                return Err(SARError::LayerLimitExceeded {
+                    count: layers.len(),
+                    limit: MAX_LAYERS,
+                });
+            }
+
+            let mut part = sa.clone();
+            *part.layers_mut() = layers;
+            parts.push(part);
+            layout = layout.with_part(
+                column as u32,
+                row as u32,
+                format!("{name}-{column}-{row}.sar"),
+            )?;
+        }
+    }
+
+    Ok(Split {
+        layout,
+        parts,
+        scale,
+        clipped_layers,
+    })
+}
+
+/// Splits a SymbolArt into the smallest grid whose parts are all within the layer budget
+///
+/// Up to four parts are tried, which is how large designs are usually posted.
+pub fn split_auto(sa: &Payload, name: &str) -> Result<Split> {
+    let mut result = Err(SARError::LayerLimitExceeded {
+        count: sa.layers().len(),
+        limit: MAX_LAYERS,
+    });
+    for (columns, rows) in AUTO_GRIDS {
+        result = split(sa, columns, rows, name);
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Layer, RawColor, SymbolArtDrawer, SymbolArtLayer, SymbolId, parse, renderer::draw::Drawer,
+        test::RAW_FILE,
+    };
+    use image::imageops;
+
+    #[test]
+    fn test_split_single_cell_is_identity() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let split = split(&sa, 1, 1, "art").unwrap();
+
+        assert_eq!(split.parts.len(), 1);
+        assert_eq!(split.layout.parts()[0].file, "art-0-0.sar");
+        let view = sa.canvas().unwrap().view();
+        let visible = sa
+            .layers()
+            .into_iter()
+            .filter(|layer| {
+                let rect = layer.bounding_box();
+                rect.max.x >= view.min.x
+                    && rect.min.x <= view.max.x
+                    && rect.max.y >= view.min.y
+                    && rect.min.y <= view.max.y
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(split.parts[0].layers(), visible);
+        assert_eq!(split.scale, 1);
+        assert_eq!(split.clipped_layers, 0);
+    }
+
+    #[test]
+    fn test_split_renders_like_the_original() {
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let p = |x, y| Position { x, y };
+        let layers = sa.layers_mut();
+        layers.clear();
+        // Discs spread over the view, several of them across the cell borders
+        for (i, (x, y)) in [
+            (40, 84),
+            (122, 100),
+            (100, 122),
+            (122, 122),
+            (200, 150),
+            (110, 80),
+            (60, 160),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut disc = Layer::new(SymbolId::new(731));
+            disc.set_vertices([p(x, y), p(x, y + 12), p(x + 12, y), p(x + 12, y + 12)]);
+            disc.set_raw_color(RawColor::new(7, 6 * i as u8, 63 - 6 * i as u8, 32));
+            layers.push(disc);
+        }
+
+        // Composite in strict layer order, so chunking does not round differently
+        let drawer = SymbolArtDrawer::new().with_chunk_size(usize::MAX);
+        let original = drawer.draw(&sa).unwrap();
+
+        let halves = split(&sa, 2, 1, "art").unwrap();
+        assert_eq!(halves.scale, 1);
+        assert_eq!(halves.clipped_layers, 0);
+        let stitched = drawer
+            .draw_layout(&halves.layout, &halves.parts, 1.0)
+            .unwrap();
+        let left = (stitched.width() - original.width()) / 2;
+        let centered = imageops::crop_imm(&stitched, left, 0, original.width(), original.height());
+        assert_eq!(centered.to_image(), original);
+
+        let quarters = split(&sa, 2, 2, "art").unwrap();
+        assert_eq!(quarters.scale, 2);
+        assert_eq!(quarters.clipped_layers, 0);
+        let stitched = drawer
+            .draw_layout(&quarters.layout, &quarters.parts, 1.0)
+            .unwrap();
+        assert_eq!(stitched, drawer.draw_with_scale(&sa, 2.0).unwrap());
+
+        // At twice the size, this disc reaches too far past the border of the
+        // left cells to fit their coordinate space
+        let mut disc = Layer::new(SymbolId::new(731));
+        disc.set_vertices([p(120, 100), p(120, 124), p(144, 100), p(144, 124)]);
+        sa.layers_mut().push(disc);
+        assert_eq!(split(&sa, 2, 2, "art").unwrap().clipped_layers, 1);
+    }
+
+    #[test]
+    fn test_split_auto_over_budget() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let mut over_budget = sa.clone();
+        let mut layers = sa.layers();
+        layers.extend(sa.layers());
+        layers.extend(sa.layers());
+        *over_budget.layers_mut() = layers;
+        assert!(over_budget.layers().len() > MAX_LAYERS);
+
+        let split = split_auto(&over_budget, "art").unwrap();
+        assert!(split.parts.len() > 1);
+        assert_eq!(split.parts.len(), split.layout.parts().len());
+        assert!(
+            split
+                .parts
+                .iter()
+                .all(|part| part.layers().len() <= MAX_LAYERS)
+        );
+
+        // Every part must be encodable on its own
+        for part in &split.parts {
+            crate::encode(part, crate::Compression::Compressed).unwrap();
+        }
+    }
+}
@@ -5,9 +5,19 @@
 //! ## Overview
 //!
 //! This library provides functionality to:
-//! - Parse SAR files into a structured format
+//! - Parse SAR files into a structured format, and encode them back
 //! - Render SymbolArt compositions into images
 //! - Manipulate and inspect SymbolArt properties
+//! - Select layers by symbol, color, region and visibility (see [`query`])
+//! - Edit compositions, such as merging two SymbolArts (see [`edit`])
+//! - Split large designs into multi-part layouts and render them (see [`layout`])
+//! - Author layers with sub-pixel corners and snap them to the grid (see [`authoring`])
+//! - Analyze the colors and layer visibility of a composition (see [`analysis`])
+//! - Scrub author IDs and name known authors (see [`author`])
+//! - Name files like the game cache and hash their content (see [`fingerprint`])
+//! - Check an art against the format and the game's constraints (see [`lint`])
+//! - Compare two arts layer by layer (see [`diff`])
+//! - Aggregate statistics over a corpus of files (see [`stats`])
 //!
 //! ## Basic Usage
 //!
@@ -42,7 +52,7 @@
 //! Each layer in a SymbolArt has the following properties:
 //! - Position: Defined by four corner points (top-left, bottom-left, top-right, bottom-right)
 //! - Symbol: A unique identifier for the symbol used in the layer
-//! - Color: RGBA color values
+//! - Color: RGBA color values, stored as 6-bit channels and a 3-bit alpha
 //! - Visibility: Whether the layer is hidden or visible
 //!
 //! ## Advanced Usage
@@ -137,14 +147,30 @@
 //!
 //! This project is licensed under the MIT License - see the LICENSE file for details.
 
+pub mod analysis;
+pub mod author;
+pub mod authoring;
 mod core;
+pub mod diff;
+pub mod edit;
+pub mod fingerprint;
+pub mod layout;
+pub mod lint;
 mod parser;
+pub mod query;
 pub mod renderer;
-pub use core::result::Result;
-pub use core::sa::{Color, SymbolArt, SymbolArtLayer};
-pub use parser::payload::parse;
-pub use renderer::SymbolArtDrawer;
+pub mod stats;
+pub use core::color::{ColorSpace, Quantized};
+pub use core::result::{Result, SARError};
+pub use core::sa::{
+    ALPHA_LEVELS, CANVAS_SIZE, COLOR_LEVELS, Canvas, Color, MAX_LAYERS, Position, RawColor, Rect,
+    SymbolArt, SymbolArtLayer,
+};
+pub use core::symbol::{Symbol, SymbolId};
+pub use parser::decode::Compression;
+pub use parser::payload::{Layer, Payload, encode, parse};
 pub use renderer::default::draw;
+pub use renderer::{BlendSpace, LayerSelection, SymbolArtDrawer};
 
 #[cfg(test)]
 mod test;
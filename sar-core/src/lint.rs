@@ -0,0 +1,390 @@
+//! Checks a SymbolArt against the format and the game's constraints
+//!
+//! [`lint`] returns a [`Finding`] for every problem, tagged with the [`Rule`] it
+//! breaks, a [`Severity`] and, for layer problems, the index of the layer
+//! counted from the top.
+//!
+//! ```
+//! use sar_core::{lint::{Severity, lint}, parse};
+//!
+//! let bytes = include_bytes!("../../fixture/sa0a1d081b8a108bb8c9847c4cd83db662.sar");
+//! let sa = parse(Vec::from(*bytes)).unwrap();
+//! assert!(lint(&sa).iter().all(|finding| finding.severity < Severity::Error));
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::core::{
+    result::SARError,
+    sa::{MAX_LAYERS, Position, SymbolArt, SymbolArtLayer},
+};
+use crate::parser::payload::{NAME_MAX_LENGTH, Payload, parse};
+use crate::renderer::resource::Resource;
+
+/// How serious a finding is, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// Likely intended, but worth knowing
+    Info,
+    /// The art loads, but part of it is wasted or will not show as expected
+    Warning,
+    /// The game rejects the art or cannot draw part of it
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = SARError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(SARError::InvalidSeverity(s.to_string())),
+        }
+    }
+}
+
+/// The constraint a finding breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Rule {
+    /// The file cannot be parsed at all
+    Unparseable,
+    /// More layers than the game allows
+    LayerLimit,
+    /// The layer count in the header differs from the layers in the body
+    LayerCountMismatch,
+    /// The header describes neither a standard canvas nor an alliance flag
+    UnknownCanvas,
+    /// The symbol has no entry in the symbol sheets
+    UnknownSymbol,
+    /// The quad has no area
+    DegenerateQuad,
+    /// Two edges of the quad cross each other
+    SelfIntersectingQuad,
+    /// The quad lies entirely outside the visible canvas
+    OffCanvas,
+    /// The layer is fully transparent
+    ZeroAlpha,
+    /// The file stores a longer name than the format allows
+    NameTooLong,
+    /// The name holds a character the game cannot show
+    NameCharacter,
+}
+
+impl Rule {
+    /// Returns the severity of findings for this rule
+    pub fn severity(&self) -> Severity {
+        match self {
+            Rule::Unparseable
+            | Rule::LayerLimit
+            | Rule::LayerCountMismatch
+            | Rule::UnknownCanvas
+            | Rule::UnknownSymbol
+            | Rule::NameTooLong => Severity::Error,
+            Rule::DegenerateQuad | Rule::OffCanvas | Rule::ZeroAlpha | Rule::NameCharacter => {
+                Severity::Warning
+            }
+            Rule::SelfIntersectingQuad => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Rule::Unparseable => "unparseable",
+            Rule::LayerLimit => "layer-limit",
+            Rule::LayerCountMismatch => "layer-count-mismatch",
+            Rule::UnknownCanvas => "unknown-canvas",
+            Rule::UnknownSymbol => "unknown-symbol",
+            Rule::DegenerateQuad => "degenerate-quad",
+            Rule::SelfIntersectingQuad => "self-intersecting-quad",
+            Rule::OffCanvas => "off-canvas",
+            Rule::ZeroAlpha => "zero-alpha",
+            Rule::NameTooLong => "name-too-long",
+            Rule::NameCharacter => "name-character",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A problem found in a SymbolArt
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    /// The index of the offending layer, counted from the top
+    pub layer: Option<usize>,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(rule: Rule, layer: Option<usize>, message: String) -> Self {
+        Self {
+            rule,
+            severity: rule.severity(),
+            layer,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.layer {
+            Some(layer) => write!(
+                f,
+                "{}: layer {layer}: {} [{}]",
+                self.severity, self.message, self.rule
+            ),
+            None => write!(f, "{}: {} [{}]", self.severity, self.message, self.rule),
+        }
+    }
+}
+
+/// Parses and checks a SAR file
+///
+/// A file that fails to parse yields a single [`Rule::Unparseable`] finding, so
+/// corrupt files are reported like any other error.
+pub fn lint_file(bytes: impl Into<Box<[u8]>>) -> Vec<Finding> {
+    match parse(bytes) {
+        Ok(sa) => lint(&sa),
+        Err(e) => vec![Finding::new(Rule::Unparseable, None, e.to_string())],
+    }
+}
+
+/// Checks a parsed SymbolArt, returning the findings in layer order
+///
+/// The header layer count is the one recorded when the file was parsed, so
+/// this is meant for arts straight from [`parse`].
+pub fn lint(sa: &Payload) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let layers = sa.layers();
+
+    if layers.len() > MAX_LAYERS {
+        findings.push(Finding::new(
+            Rule::LayerLimit,
+            None,
+            format!("{} layers exceed the limit of {MAX_LAYERS}", layers.len()),
+        ));
+    }
+    if sa.declared_layers() as usize != layers.len() {
+        findings.push(Finding::new(
+            Rule::LayerCountMismatch,
+            None,
+            format!(
+                "the header declares {} layers but the body holds {}",
+                sa.declared_layers(),
+                layers.len()
+            ),
+        ));
+    }
+    let view = match sa.canvas() {
+        Some(canvas) => Some(canvas.view()),
+        None => {
+            findings.push(Finding::new(
+                Rule::UnknownCanvas,
+                None,
+                "the header describes an unknown canvas".to_string(),
+            ));
+            None
+        }
+    };
+
+    for (i, layer) in layers.iter().enumerate() {
+        let symbol = layer.symbol().id();
+        if !Resource::contains(symbol) {
+            findings.push(Finding::new(
+                Rule::UnknownSymbol,
+                Some(i),
+                format!("symbol {symbol} has no image"),
+            ));
+        }
+        if layer.area() == 0.0 {
+            findings.push(Finding::new(
+                Rule::DegenerateQuad,
+                Some(i),
+                "the quad has no area".to_string(),
+            ));
+        } else if is_self_intersecting(layer) {
+            findings.push(Finding::new(
+                Rule::SelfIntersectingQuad,
+                Some(i),
+                "the edges of the quad cross".to_string(),
+            ));
+        }
+        if view.is_some_and(|view| !view.intersects(&layer.bounding_box())) {
+            findings.push(Finding::new(
+                Rule::OffCanvas,
+                Some(i),
+                "the quad is outside the canvas".to_string(),
+            ));
+        }
+        if layer.raw_color().a == 0 {
+            findings.push(Finding::new(
+                Rule::ZeroAlpha,
+                Some(i),
+                "the layer is fully transparent".to_string(),
+            ));
+        }
+    }
+
+    let name = sa.name_units();
+    if sa.stored_name_length() > NAME_MAX_LENGTH {
+        findings.push(Finding::new(
+            Rule::NameTooLong,
+            None,
+            format!(
+                "the file stores {} characters for the name, more than the {NAME_MAX_LENGTH} allowed",
+                sa.stored_name_length()
+            ),
+        ));
+    }
+    for c in char::decode_utf16(name.iter().copied()) {
+        let message = match c {
+            Err(e) => format!(
+                "the name has an unpaired surrogate {:#06x}",
+                e.unpaired_surrogate()
+            ),
+            Ok(c) if c.is_control() => format!("the name has a control character {:?}", c),
+            Ok(c) if c as u32 > 0xffff => format!("the name has an unsupported character {c}"),
+            Ok(_) => continue,
+        };
+        findings.push(Finding::new(Rule::NameCharacter, None, message));
+    }
+
+    findings
+}
+
+/// Returns whether opposite edges of the quad cross, making a bow tie
+fn is_self_intersecting<L: SymbolArtLayer>(layer: &L) -> bool {
+    let (tl, tr, br, bl) = (
+        layer.top_left(),
+        layer.top_right(),
+        layer.bottom_right(),
+        layer.bottom_left(),
+    );
+    segments_cross(tl, tr, br, bl) || segments_cross(tr, br, bl, tl)
+}
+
+/// Returns whether the segments `a`-`b` and `c`-`d` properly cross
+fn segments_cross(a: Position, b: Position, c: Position, d: Position) -> bool {
+    let orientation = |p: Position, q: Position, r: Position| {
+        let value = (q.x as i32 - p.x as i32) * (r.y as i32 - p.y as i32)
+            - (q.y as i32 - p.y as i32) * (r.x as i32 - p.x as i32);
+        value.signum()
+    };
+    let (o1, o2) = (orientation(a, b, c), orientation(a, b, d));
+    let (o3, o4) = (orientation(c, d, a), orientation(c, d, b));
+    o1 * o2 < 0 && o3 * o4 < 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Layer, SymbolId, test::RAW_FILE};
+
+    fn rules(findings: &[Finding]) -> Vec<(Rule, Option<usize>)> {
+        findings.iter().map(|f| (f.rule, f.layer)).collect()
+    }
+
+    #[test]
+    fn test_lint_clean_fixture() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        assert!(lint(&sa).iter().all(|f| f.severity < Severity::Error));
+    }
+
+    #[test]
+    fn test_lint_layers() {
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let p = |x, y| Position { x, y };
+
+        let mut unknown = Layer::new(SymbolId::new(100));
+        unknown.set_vertices([p(100, 100), p(100, 120), p(120, 100), p(120, 120)]);
+        let mut degenerate = Layer::new(SymbolId::new(1));
+        degenerate.set_vertices([p(100, 100); 4]);
+        let mut bow_tie = Layer::new(SymbolId::new(1));
+        // The bottom corners are swapped, so the left and right edges cross
+        bow_tie.set_vertices([p(100, 100), p(125, 140), p(120, 100), p(100, 120)]);
+        let mut off_canvas = Layer::new(SymbolId::new(1));
+        off_canvas.set_vertices([p(0, 0), p(0, 10), p(10, 0), p(10, 10)]);
+        let mut transparent = off_canvas;
+        transparent.set_vertices(unknown.vertices());
+        let mut color = transparent.raw_color();
+        color.a = 0;
+        transparent.set_raw_color(color);
+
+        let layers = sa.layers_mut();
+        layers.clear();
+        layers.extend([unknown, degenerate, bow_tie, off_canvas, transparent]);
+
+        let findings = lint(&sa);
+        assert_eq!(
+            rules(&findings),
+            vec![
+                (Rule::LayerCountMismatch, None),
+                (Rule::UnknownSymbol, Some(0)),
+                (Rule::DegenerateQuad, Some(1)),
+                (Rule::SelfIntersectingQuad, Some(2)),
+                (Rule::OffCanvas, Some(3)),
+                (Rule::ZeroAlpha, Some(4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_name() {
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        sa.set_name("tab\there 🐱");
+        let findings = lint(&sa);
+        assert_eq!(
+            rules(&findings),
+            vec![(Rule::NameCharacter, None), (Rule::NameCharacter, None)]
+        );
+
+        let mut body = parse(Vec::from(RAW_FILE)).unwrap().to_bytes();
+        body.extend(
+            "-too-long-for-the-format"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        let sa = Payload::parse(&body).unwrap();
+        assert_eq!(rules(&lint(&sa)), vec![(Rule::NameTooLong, None)]);
+    }
+
+    #[test]
+    fn test_lint_file() {
+        assert_eq!(
+            lint_file(RAW_FILE),
+            lint(&parse(Vec::from(RAW_FILE)).unwrap())
+        );
+
+        let findings = lint_file(&b"SAR"[..]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, Rule::Unparseable);
+        assert_eq!(findings[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_severity() {
+        assert!(Severity::Info < Severity::Warning);
+        assert_eq!("warning".parse::<Severity>().unwrap(), Severity::Warning);
+        assert!("fatal".parse::<Severity>().is_err());
+    }
+}
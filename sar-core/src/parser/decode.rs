@@ -7,7 +7,7 @@ use blowfish::{
     BlowfishLE,
 };
 
-const KEY: &[u8] = &[0x09, 0x07, 0xc1, 0x2b];
+pub(super) const KEY: &[u8] = &[0x09, 0x07, 0xc1, 0x2b];
 
 pub fn decrypt(bytes: &mut [u8]) {
     // It's safe to unwrap because the key is hardcoded and known
@@ -30,7 +30,8 @@ pub fn decompress(bytes: &mut [u8]) -> Result<Box<[u8]>> {
     Ok(Box::from(result))
 }
 
-#[derive(Debug, PartialEq)]
+/// The compression mode of a SAR file, stored in the fourth byte of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Compression {
     None,
     Compressed,
@@ -0,0 +1,73 @@
+use std::io::Write;
+
+use crate::core::result::Result;
+use ages_prs::ModernPrsEncoder;
+use blowfish::{
+    BlowfishLE,
+    cipher::{BlockEncrypt, KeyInit, generic_array::GenericArray},
+};
+
+use super::decode::{Compression, KEY};
+
+pub fn encrypt(bytes: &mut [u8]) {
+    // It's safe to unwrap because the key is hardcoded and known
+    let cipher = BlowfishLE::new_from_slice(KEY).unwrap();
+    // encrypt the maximum multiple of 8 bytes, the remainder is left as is
+    for block in bytes.chunks_exact_mut(8) {
+        let block = GenericArray::from_mut_slice(block);
+        cipher.encrypt_block(block);
+    }
+}
+
+pub fn compress(bytes: &[u8]) -> Result<Box<[u8]>> {
+    // compress the PRS
+    let mut encoder = ModernPrsEncoder::new(Vec::new());
+    encoder.write_all(bytes)?;
+    let mut result = encoder
+        .into_inner()
+        .map_err(|e| std::io::Error::new(e.error().kind(), e.to_string()))?;
+    // XOR every byte in the buffer with 0x95
+    result.iter_mut().for_each(|b| *b ^= 0x95);
+
+    Ok(Box::from(result))
+}
+
+/// Builds the 4 byte file header for the given compression
+pub fn format_header(compression: &Compression) -> [u8; 4] {
+    match compression {
+        Compression::Compressed => [b's', b'a', b'r', 0x84],
+        Compression::None => [b's', b'a', b'r', 0x04],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::decode;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let original = (0..20).collect::<Vec<u8>>();
+        let mut bytes = original.clone();
+        encrypt(&mut bytes);
+        assert_ne!(bytes, original);
+        decode::decrypt(&mut bytes);
+        assert_eq!(bytes, original);
+    }
+
+    #[test]
+    fn test_compress_roundtrip() {
+        let original = b"symbol art symbol art symbol art".to_vec();
+        let mut compressed = compress(&original).unwrap();
+        let decompressed = decode::decompress(&mut compressed).unwrap();
+        assert_eq!(&decompressed[..], &original[..]);
+    }
+
+    #[test]
+    fn test_format_header() {
+        for compression in [Compression::None, Compression::Compressed] {
+            let header = format_header(&compression);
+            assert_eq!(decode::validate_format(&header).unwrap(), compression);
+        }
+    }
+}
@@ -1,2 +1,3 @@
-mod decode;
+pub(crate) mod decode;
+mod encode;
 pub(crate) mod payload;
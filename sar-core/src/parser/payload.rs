@@ -1,18 +1,53 @@
 use crate::{
     core::{
-        result::Result,
-        sa::{self, Position, SymbolArt, SymbolArtLayer},
+        result::{Result, SARError},
+        sa::{self, CANVAS_SIZE, Canvas, Position, SymbolArt, SymbolArtLayer},
         symbol,
     },
-    parser::decode,
+    parser::{
+        decode::{self, Compression},
+        encode,
+    },
 };
 
+/// Size of the header in bytes
+const HEADER_SIZE: usize = 8;
+/// Size of a single layer in bytes
+const LAYER_SIZE: usize = 16;
+/// Maximum number of UTF-16 code units in the name
+pub(crate) const NAME_MAX_LENGTH: usize = 13;
+
 /// Parses a byte array into a Payload structure
-pub fn parse(bytes: impl Into<Box<[u8]>>) -> Result<impl SymbolArt + std::fmt::Debug> {
+pub fn parse(bytes: impl Into<Box<[u8]>>) -> Result<Payload> {
     let body = get_body(bytes.into())?;
     Payload::parse(&body)
 }
 
+/// Encodes a Payload structure into the bytes of a SAR file
+///
+/// Returns [`SARError::LayerLimitExceeded`] when the payload holds more than
+/// [`MAX_LAYERS`](sa::MAX_LAYERS) layers, since the header could not record them.
+pub fn encode(payload: &Payload, compression: Compression) -> Result<Vec<u8>> {
+    if payload.layers.len() > sa::MAX_LAYERS {
+        return Err(SARError::LayerLimitExceeded {
+            count: payload.layers.len(),
+            limit: sa::MAX_LAYERS,
+        });
+    }
+
+    let body = payload.to_bytes();
+    let mut body = match compression {
+        Compression::None => Box::from(body),
+        Compression::Compressed => encode::compress(&body)?,
+    };
+    encode::encrypt(&mut body);
+
+    let mut bytes = Vec::with_capacity(4 + body.len());
+    bytes.extend_from_slice(&encode::format_header(&compression));
+    bytes.extend_from_slice(&body);
+    Ok(bytes)
+}
+
 /// Extracts and decompresses the body of the SAR file
 fn get_body(mut bytes: Box<[u8]>) -> Result<Box<[u8]>> {
     let compression = decode::validate_format(&bytes)?;
@@ -34,40 +69,114 @@ pub struct Payload {
     layers: Vec<Layer>,
     /// Name of the SAR file in UTF-16LE format (up to 13 characters)
     name: Vec<u16>,
+    /// The number of UTF-16 code units stored for the name, before truncation
+    stored_name_length: usize,
 }
 
 impl Payload {
     /// Parses a byte slice into a Payload structure
     pub fn parse(bytes: &[u8]) -> Result<Self> {
-        let header = Header::parse(&bytes[0..std::mem::size_of::<Header>()])?;
-        let layers = Layers::parse(&bytes[std::mem::size_of::<Header>()..])?.into();
-        let name = Self::parse_name(bytes, &header)?;
+        if bytes.len() < HEADER_SIZE {
+            return Err(SARError::InvalidFileHeader);
+        }
+        let header = Header::parse(&bytes[0..HEADER_SIZE])?;
+        let layers = Layers::parse(&bytes[HEADER_SIZE..], header.layers())?.into();
+        let mut name = Self::parse_name(bytes, &header)?;
+        let stored_name_length = name.len();
+        name.truncate(NAME_MAX_LENGTH);
 
         Ok(Self {
             header,
             layers,
             name,
+            stored_name_length,
         })
     }
 
-    /// Parses the name field from the byte slice
+    /// Parses the name field from the byte slice, including any code units past
+    /// the limit
     fn parse_name(bytes: &[u8], header: &Header) -> Result<Vec<u16>> {
-        let size_of_header = std::mem::size_of::<Header>();
-        let size_of_layer = std::mem::size_of::<Layer>();
-        let start = size_of_header + size_of_layer * header.layers() as usize;
+        let start = HEADER_SIZE + LAYER_SIZE * header.layers() as usize;
 
         let name_bytes = bytes[usize::min(start, bytes.len())..]
             .chunks_exact(2)
-            .take(13) // Name is at most 13 chars
             .map(|b| u16::from_le_bytes(b.try_into().unwrap()))
             .collect::<Vec<_>>();
 
         Ok(name_bytes)
     }
+
+    /// Serializes the Payload into the decrypted, uncompressed body of a SAR file
+    ///
+    /// The layer count in the header is always written from the actual layers,
+    /// wrapping past 255, so [`encode()`] checks the count first.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(HEADER_SIZE + LAYER_SIZE * self.layers.len() + self.name.len() * 2);
+        bytes.extend_from_slice(&self.header.author_id.to_be_bytes());
+        bytes.push(self.layers.len() as u8);
+        bytes.push(self.header.height);
+        bytes.push(self.header.width);
+        bytes.push(self.header.sound_effect);
+        for layer in &self.layers {
+            bytes.extend_from_slice(&layer.to_bytes());
+        }
+        for c in &self.name {
+            bytes.extend_from_slice(&c.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Returns the canvas bytes of the header as height and width
+    pub(crate) fn canvas_bytes(&self) -> (u8, u8) {
+        (self.header.height, self.header.width)
+    }
+
+    /// Returns the layer count recorded in the header when the payload was parsed
+    pub(crate) fn declared_layers(&self) -> u8 {
+        self.header.layers
+    }
+
+    /// Returns the name as UTF-16 code units
+    pub(crate) fn name_units(&self) -> &[u16] {
+        &self.name
+    }
+
+    /// Returns the number of UTF-16 code units the file stored for the name
+    ///
+    /// Only the first 13 are kept in the name, so this tells when some were cut.
+    pub(crate) fn stored_name_length(&self) -> usize {
+        self.stored_name_length
+    }
+
+    /// Returns a mutable reference to the layers, ordered from top to bottom
+    pub fn layers_mut(&mut self) -> &mut Vec<Layer> {
+        &mut self.layers
+    }
+
+    pub fn set_author_id(&mut self, author_id: u32) {
+        self.header.author_id = author_id;
+    }
+
+    /// Sets the canvas bytes of the header
+    pub fn set_canvas(&mut self, canvas: Canvas) {
+        (self.header.height, self.header.width) = match canvas {
+            Canvas::Standard => (HEADER_SIZE_NORMAL, HEADER_WIDTH_NORMAL),
+            Canvas::TeamFlag => (HEADER_SIZE_TEAM_FLAG, HEADER_WIDTH_TEAM_FLAG),
+        };
+    }
+
+    /// Sets the name, truncated to the 13 UTF-16 code units the format allows
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.encode_utf16().take(NAME_MAX_LENGTH).collect();
+        self.stored_name_length = self.name.len();
+    }
 }
 
 const HEADER_SIZE_TEAM_FLAG: u8 = 0x40;
 const HEADER_SIZE_NORMAL: u8 = 0x80;
+const HEADER_WIDTH_TEAM_FLAG: u8 = 0x40;
+const HEADER_WIDTH_NORMAL: u8 = 0xc1;
 
 impl SymbolArt for Payload {
     type Layer = Layer;
@@ -96,6 +205,19 @@ impl SymbolArt for Payload {
         self.layers.clone()
     }
 
+    /// Returns the canvas described by the header, if it is a known one
+    fn canvas(&self) -> Option<Canvas> {
+        match self.header.height {
+            HEADER_SIZE_NORMAL => Some(Canvas::Standard),
+            HEADER_SIZE_TEAM_FLAG => Some(Canvas::TeamFlag),
+            _ => None,
+        }
+    }
+
+    fn sound_effect(&self) -> u8 {
+        self.header.sound_effect
+    }
+
     fn name(&self) -> String {
         String::from_utf16_lossy(&self.name)
     }
@@ -139,10 +261,11 @@ pub struct Layers {
 }
 
 impl Layers {
-    /// Parses a byte slice into a Layers structure
-    pub(super) fn parse(bytes: &[u8]) -> Result<Self> {
+    /// Parses a byte slice into a Layers structure holding at most `count` layers
+    pub(super) fn parse(bytes: &[u8], count: u8) -> Result<Self> {
         let layers = bytes
-            .chunks_exact(std::mem::size_of::<Layer>())
+            .chunks_exact(LAYER_SIZE)
+            .take(count as usize)
             .map(Layer::parse)
             .collect::<Result<Vec<_>>>()?;
 
@@ -179,6 +302,8 @@ pub struct Layer {
     pub(super) color_g: u8,
     /// Blue color component
     pub(super) color_b: u8,
+    /// Trailing layer data not used for rendering, kept as is for writing
+    pub(super) extra: u32,
 }
 
 // Bit masks for layer data
@@ -190,6 +315,25 @@ const MASK_COLOR_G: u32 = 0b00000000000000000000111111000000;
 const MASK_COLOR_B: u32 = 0b00000000000000111111000000000000;
 
 impl Layer {
+    /// Creates a visible, opaque white layer with all corners at the origin
+    pub fn new(symbol_id: symbol::SymbolId) -> Self {
+        let mut layer = Self {
+            top_left: Position { x: 0, y: 0 },
+            bottom_left: Position { x: 0, y: 0 },
+            top_right: Position { x: 0, y: 0 },
+            bottom_right: Position { x: 0, y: 0 },
+            is_hidden: false,
+            symbol_id: 0,
+            alpha: (MASK_ALPHA >> 18) as u8,
+            color_r: MASK_COLOR_R as u8,
+            color_g: (MASK_COLOR_G >> 6) as u8,
+            color_b: (MASK_COLOR_B >> 12) as u8,
+            extra: 0,
+        };
+        layer.set_symbol(symbol_id);
+        layer
+    }
+
     /// Parses a byte slice into a Layer structure
     fn parse(bytes: &[u8]) -> Result<Self> {
         let top_left = Position::parse(&bytes[0..2])?;
@@ -198,6 +342,7 @@ impl Layer {
         let bottom_right = Position::parse(&bytes[6..8])?;
 
         let layer_data = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        let extra = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
 
         Ok(Self {
             top_left,
@@ -210,9 +355,97 @@ impl Layer {
             color_r: Self::extract_color_r(layer_data),
             color_g: Self::extract_color_g(layer_data),
             color_b: Self::extract_color_b(layer_data),
+            extra,
         })
     }
 
+    /// Serializes the layer into its 16 byte representation
+    fn to_bytes(self) -> [u8; LAYER_SIZE] {
+        let mut bytes = [0; LAYER_SIZE];
+        for (i, position) in self.vertices().iter().enumerate() {
+            bytes[i * 2] = position.x;
+            bytes[i * 2 + 1] = position.y;
+        }
+        bytes[8..12].copy_from_slice(&self.layer_data().to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.extra.to_le_bytes());
+        bytes
+    }
+
+    /// Packs the hidden flag, symbol ID, alpha and color into the layer data
+    fn layer_data(&self) -> u32 {
+        let mut layer_data = 0;
+        if self.is_hidden {
+            layer_data |= LAYER_IS_HIDDEN;
+        }
+        layer_data |= ((self.symbol_id as u32) << 21) & MASK_SYMBOL_ID;
+        layer_data |= ((self.alpha as u32) << 18) & MASK_ALPHA;
+        layer_data |= (self.color_r as u32) & MASK_COLOR_R;
+        layer_data |= ((self.color_g as u32) << 6) & MASK_COLOR_G;
+        layer_data |= ((self.color_b as u32) << 12) & MASK_COLOR_B;
+        layer_data
+    }
+
+    /// Returns the corners in file order: top-left, bottom-left, top-right, bottom-right
+    pub fn vertices(&self) -> [Position; 4] {
+        [
+            self.top_left,
+            self.bottom_left,
+            self.top_right,
+            self.bottom_right,
+        ]
+    }
+
+    /// Sets the corners in file order: top-left, bottom-left, top-right, bottom-right
+    pub fn set_vertices(&mut self, vertices: [Position; 4]) {
+        [
+            self.top_left,
+            self.bottom_left,
+            self.top_right,
+            self.bottom_right,
+        ] = vertices;
+    }
+
+    /// Sets the symbol, keeping only the bits the format can store
+    pub fn set_symbol(&mut self, symbol_id: symbol::SymbolId) {
+        self.symbol_id = (symbol_id.id() & (MASK_SYMBOL_ID >> 21)) as u16;
+    }
+
+    /// Sets the color, keeping only the bits the format can store
+    pub fn set_raw_color(&mut self, color: sa::RawColor) {
+        self.alpha = color.a & (MASK_ALPHA >> 18) as u8;
+        self.color_r = color.r & MASK_COLOR_R as u8;
+        self.color_g = color.g & (MASK_COLOR_G >> 6) as u8;
+        self.color_b = color.b & (MASK_COLOR_B >> 12) as u8;
+    }
+
+    pub fn set_hidden(&mut self, is_hidden: bool) {
+        self.is_hidden = is_hidden;
+    }
+
+    /// Maps every corner through `f`, rounding and clamping the result to the coordinate space
+    ///
+    /// Returns the new layer and whether any corner had to be clamped.
+    pub(crate) fn map_vertices(&self, f: impl Fn(f32, f32) -> (f32, f32)) -> (Layer, bool) {
+        let max = (CANVAS_SIZE - 1) as f32;
+        let mut clamped = false;
+        let mut vertices = self.vertices();
+        for vertex in vertices.iter_mut() {
+            let (x, y) = f(vertex.x as f32, vertex.y as f32);
+            let (x, y) = (x.round(), y.round());
+            if !(0.0..=max).contains(&x) || !(0.0..=max).contains(&y) {
+                clamped = true;
+            }
+            *vertex = Position {
+                x: x.clamp(0.0, max) as u8,
+                y: y.clamp(0.0, max) as u8,
+            };
+        }
+
+        let mut layer = *self;
+        layer.set_vertices(vertices);
+        (layer, clamped)
+    }
+
     /// Extracts the hidden flag from the layer data
     fn extract_is_hidden(layer_data: u32) -> bool {
         (layer_data & LAYER_IS_HIDDEN) != 0
@@ -244,20 +477,6 @@ impl Layer {
     }
 }
 
-/// The factor used to convert the alpha value to a 8-bit value.
-/// SAR files use a 3-bit alpha value, so we need to scale it up to 8-bit
-///
-/// We use 37 to avoid floating point arithmetic for better performance
-/// 255 / 7 = 36.4285714286
-const ALPHA_FACTOR: u8 = 37;
-
-/// The factor used to convert the color value to a 8-bit value.
-/// SAR files use a 4-bit color value, so we need to scale it up to 8-bit
-///
-/// We use 4 to avoid floating point arithmetic for better performance
-/// 255 / 63 = 4.0476190476
-const COLOR_FACTOR: u8 = 4;
-
 impl SymbolArtLayer for Layer {
     fn top_left(&self) -> Position {
         self.top_left
@@ -280,16 +499,16 @@ impl SymbolArtLayer for Layer {
     }
 
     fn color(&self) -> sa::Color {
-        let a = self.alpha.saturating_mul(ALPHA_FACTOR);
-        let r = self.color_r.saturating_mul(COLOR_FACTOR);
-        let g = self.color_g.saturating_mul(COLOR_FACTOR);
-        let b = self.color_b.saturating_mul(COLOR_FACTOR);
-        sa::Color::new(a, r, g, b)
+        self.raw_color().expand()
     }
 
     fn is_hidden(&self) -> bool {
         self.is_hidden
     }
+
+    fn raw_color(&self) -> sa::RawColor {
+        sa::RawColor::new(self.alpha, self.color_r, self.color_g, self.color_b)
+    }
 }
 
 impl Position {
@@ -344,10 +563,12 @@ mod tests {
                     color_r: 0,
                     color_g: 0,
                     color_b: 0,
+                    extra: 0,
                 };
                 104
             ],
             name: expected_name.to_vec(),
+            stored_name_length: expected_name.len(),
         };
 
         assert_eq!(payload.header, expected.header);
@@ -365,5 +586,50 @@ mod tests {
             payload.name,
             vec![84, 104, 97, 110, 107, 32, 121, 111, 117, 32, 33, 33]
         );
+        assert_eq!(payload.layers.len(), payload.header.layers as usize);
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let payload = parse(Vec::from(RAW_FILE)).unwrap();
+        for compression in [Compression::None, Compression::Compressed] {
+            let bytes = encode(&payload, compression).unwrap();
+            assert_eq!(decode::validate_format(&bytes).unwrap(), compression);
+            assert_eq!(parse(bytes).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn test_parse_name_keeps_the_format_limit() {
+        let mut body = parse(Vec::from(RAW_FILE)).unwrap().to_bytes();
+        body.extend("0123456789".encode_utf16().flat_map(u16::to_le_bytes));
+        let payload = Payload::parse(&body).unwrap();
+        assert_eq!(payload.name(), "なかたさん01234567");
+        assert_eq!(payload.stored_name_length(), 15);
+    }
+
+    #[test]
+    fn test_encode_layer_limit() {
+        let mut payload = parse(Vec::from(RAW_FILE)).unwrap();
+        let layer = payload.layers()[0];
+        *payload.layers_mut() = vec![layer; 256];
+        assert!(matches!(
+            encode(&payload, Compression::None),
+            Err(SARError::LayerLimitExceeded {
+                count: 256,
+                limit: sa::MAX_LAYERS
+            })
+        ));
+
+        payload.layers_mut().truncate(sa::MAX_LAYERS);
+        let bytes = encode(&payload, Compression::Compressed).unwrap();
+        assert_eq!(parse(bytes).unwrap().layers().len(), sa::MAX_LAYERS);
+    }
+
+    #[test]
+    fn test_to_bytes_matches_body() {
+        let body = get_body(Box::from(RAW_FILE)).unwrap();
+        let payload = Payload::parse(&body).unwrap();
+        assert_eq!(payload.to_bytes(), body.to_vec());
     }
 }
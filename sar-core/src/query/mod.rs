@@ -0,0 +1,222 @@
+//! Layer selection queries
+//!
+//! A [`Predicate`] describes which layers of a SymbolArt should be selected.
+//! Predicates can be combined with [`Predicate::and`], [`Predicate::or`] and
+//! [`Predicate::not`], and are evaluated with [`select`] or, for region heavy
+//! workloads, against a prebuilt [`SpatialIndex`].
+//!
+//! ```no_run
+//! use sar_core::query::{self, Predicate};
+//! use sar_core::{parse, Color, Position, Rect};
+//!
+//! let bytes = std::fs::read("example.sar").unwrap();
+//! let symbol_art = parse(bytes).unwrap();
+//!
+//! // Every visible red layer inside the left half of the canvas
+//! let region = Rect::new(Position { x: 0, y: 0 }, Position { x: 127, y: 255 });
+//! let predicate = Predicate::color_within(Color::new(255, 255, 0, 0), 32.0)
+//!     .and(Predicate::hidden(false))
+//!     .and(Predicate::contained_in(region));
+//!
+//! let indices = query::select(&symbol_art, &predicate);
+//! ```
+
+mod spatial;
+
+use std::ops::{Range, RangeInclusive};
+
+use crate::core::{
+    sa::{Color, Rect, SymbolArt, SymbolArtLayer},
+    symbol::SymbolId,
+};
+
+pub use spatial::SpatialIndex;
+
+/// A composable condition evaluated against a single layer
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// Matches layers using exactly this symbol
+    Symbol(SymbolId),
+    /// Matches layers whose symbol ID falls within the range
+    SymbolRange(RangeInclusive<u32>),
+    /// Matches layers whose color is within `max_distance` of `color`
+    ///
+    /// See [`Color::distance`] for how the distance is measured.
+    ColorWithin { color: Color, max_distance: f32 },
+    /// Matches layers whose alpha falls within the range
+    AlphaRange(RangeInclusive<u8>),
+    /// Matches layers with the given hidden flag
+    Hidden(bool),
+    /// Matches layers whose bounding box intersects the region
+    Intersects(Rect),
+    /// Matches layers whose bounding box lies entirely within the region
+    ContainedIn(Rect),
+    /// Matches layers whose index falls within the range
+    IndexRange(Range<usize>),
+    /// Matches layers satisfying every inner predicate
+    All(Vec<Predicate>),
+    /// Matches layers satisfying at least one inner predicate
+    Any(Vec<Predicate>),
+    /// Matches layers not satisfying the inner predicate
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    pub fn symbol(id: SymbolId) -> Self {
+        Self::Symbol(id)
+    }
+
+    pub fn symbol_range(range: RangeInclusive<u32>) -> Self {
+        Self::SymbolRange(range)
+    }
+
+    pub fn color_within(color: Color, max_distance: f32) -> Self {
+        Self::ColorWithin {
+            color,
+            max_distance,
+        }
+    }
+
+    pub fn alpha_range(range: RangeInclusive<u8>) -> Self {
+        Self::AlphaRange(range)
+    }
+
+    pub fn hidden(hidden: bool) -> Self {
+        Self::Hidden(hidden)
+    }
+
+    pub fn intersects(region: Rect) -> Self {
+        Self::Intersects(region)
+    }
+
+    pub fn contained_in(region: Rect) -> Self {
+        Self::ContainedIn(region)
+    }
+
+    pub fn index_range(range: Range<usize>) -> Self {
+        Self::IndexRange(range)
+    }
+
+    /// Combines two predicates so that both must match
+    pub fn and(self, other: Predicate) -> Self {
+        match self {
+            Self::All(mut predicates) => {
+                predicates.push(other);
+                Self::All(predicates)
+            }
+            predicate => Self::All(vec![predicate, other]),
+        }
+    }
+
+    /// Combines two predicates so that either may match
+    pub fn or(self, other: Predicate) -> Self {
+        match self {
+            Self::Any(mut predicates) => {
+                predicates.push(other);
+                Self::Any(predicates)
+            }
+            predicate => Self::Any(vec![predicate, other]),
+        }
+    }
+
+    /// Negates the predicate
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+
+    /// Evaluates the predicate against the layer at `index`
+    pub fn matches<L>(&self, index: usize, layer: &L) -> bool
+    where
+        L: SymbolArtLayer,
+    {
+        match self {
+            Self::Symbol(id) => layer.symbol().id() == *id,
+            Self::SymbolRange(range) => range.contains(&layer.symbol().id().id()),
+            Self::ColorWithin {
+                color,
+                max_distance,
+            } => layer.color().distance(color) <= *max_distance,
+            Self::AlphaRange(range) => range.contains(&layer.color().a),
+            Self::Hidden(hidden) => layer.is_hidden() == *hidden,
+            Self::Intersects(region) => region.intersects(&layer.bounding_box()),
+            Self::ContainedIn(region) => region.contains(&layer.bounding_box()),
+            Self::IndexRange(range) => range.contains(&index),
+            Self::All(predicates) => predicates.iter().all(|p| p.matches(index, layer)),
+            Self::Any(predicates) => predicates.iter().any(|p| p.matches(index, layer)),
+            Self::Not(predicate) => !predicate.matches(index, layer),
+        }
+    }
+
+    /// Returns a region every matching layer is guaranteed to intersect, if any
+    ///
+    /// This is used to narrow down candidates through a [`SpatialIndex`].
+    fn region_hint(&self) -> Option<Rect> {
+        match self {
+            Self::Intersects(region) | Self::ContainedIn(region) => Some(*region),
+            Self::All(predicates) => predicates.iter().find_map(|p| p.region_hint()),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the indices of all layers matching the predicate, in stacking order
+pub fn select<S>(sa: &S, predicate: &Predicate) -> Vec<usize>
+where
+    S: SymbolArt,
+{
+    sa.layers()
+        .iter()
+        .enumerate()
+        .filter(|(i, layer)| predicate.matches(*i, *layer))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::sa::Position, parse, test::RAW_FILE};
+
+    #[test]
+    fn test_select_combinators() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let total = sa.layers().len();
+
+        let all = select(&sa, &Predicate::index_range(0..usize::MAX));
+        assert_eq!(all.len(), total);
+
+        let visible = select(&sa, &Predicate::hidden(false));
+        let hidden = select(&sa, &Predicate::hidden(false).not());
+        assert_eq!(visible.len() + hidden.len(), total);
+
+        let first_ten = select(
+            &sa,
+            &Predicate::index_range(0..10).and(Predicate::hidden(false)),
+        );
+        assert!(first_ten.iter().all(|i| *i < 10 && visible.contains(i)));
+    }
+
+    #[test]
+    fn test_select_symbol() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let layers = sa.layers();
+        let id = layers[0].symbol().id();
+
+        let selected = select(&sa, &Predicate::symbol(id));
+        assert!(selected.contains(&0));
+        assert!(selected.iter().all(|i| layers[*i].symbol().id() == id));
+
+        let ranged = select(&sa, &Predicate::symbol_range(id.id()..=id.id()));
+        assert_eq!(selected, ranged);
+    }
+
+    #[test]
+    fn test_select_region() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let everything = Rect::new(Position { x: 0, y: 0 }, Position { x: 255, y: 255 });
+
+        let contained = select(&sa, &Predicate::contained_in(everything));
+        assert_eq!(contained.len(), sa.layers().len());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::core::sa::{Position, Rect, SymbolArt, SymbolArtLayer};
+
+use super::Predicate;
+
+/// The width and height of a grid cell in SymbolArt coordinates
+const CELL_SIZE: usize = 16;
+/// The number of cells along each axis, covering the whole `u8` coordinate space
+const CELLS_PER_AXIS: usize = 256 / CELL_SIZE;
+
+/// A uniform grid over the SymbolArt coordinate space for fast region queries
+///
+/// Each cell stores the indices of the layers whose bounding box touches it, so
+/// region queries only need to inspect the layers near the region instead of
+/// every layer in the composition. The index is a snapshot; rebuild it after
+/// editing the layers it was built from.
+pub struct SpatialIndex<L> {
+    layers: Vec<L>,
+    bounds: Vec<Rect>,
+    cells: Vec<Vec<usize>>,
+}
+
+impl<L> SpatialIndex<L>
+where
+    L: SymbolArtLayer,
+{
+    pub fn new<S>(sa: &S) -> Self
+    where
+        S: SymbolArt<Layer = L>,
+    {
+        let layers = sa.layers();
+        let bounds = layers
+            .iter()
+            .map(|layer| layer.bounding_box())
+            .collect::<Vec<_>>();
+
+        let mut cells = vec![Vec::new(); CELLS_PER_AXIS * CELLS_PER_AXIS];
+        for (i, rect) in bounds.iter().enumerate() {
+            for cell in Self::cells_of(rect) {
+                cells[cell].push(i);
+            }
+        }
+
+        Self {
+            layers,
+            bounds,
+            cells,
+        }
+    }
+
+    /// Returns the indices of layers whose bounding box intersects the region
+    pub fn intersecting(&self, region: &Rect) -> Vec<usize> {
+        self.candidates(region)
+            .into_iter()
+            .filter(|i| region.intersects(&self.bounds[*i]))
+            .collect()
+    }
+
+    /// Returns the indices of layers whose bounding box lies within the region
+    pub fn contained_in(&self, region: &Rect) -> Vec<usize> {
+        self.candidates(region)
+            .into_iter()
+            .filter(|i| region.contains(&self.bounds[*i]))
+            .collect()
+    }
+
+    /// Returns the indices of all layers matching the predicate, in stacking order
+    ///
+    /// When the predicate restricts matches to a region, only the layers in the
+    /// cells overlapping that region are evaluated.
+    pub fn select(&self, predicate: &Predicate) -> Vec<usize> {
+        match predicate.region_hint() {
+            Some(region) => self
+                .candidates(&region)
+                .into_iter()
+                .filter(|i| predicate.matches(*i, &self.layers[*i]))
+                .collect(),
+            None => self
+                .layers
+                .iter()
+                .enumerate()
+                .filter(|(i, layer)| predicate.matches(*i, *layer))
+                .map(|(i, _)| i)
+                .collect(),
+        }
+    }
+
+    /// Collects the sorted, deduplicated indices of layers in the cells overlapping the region
+    fn candidates(&self, region: &Rect) -> Vec<usize> {
+        let mut candidates = Self::cells_of(region)
+            .flat_map(|cell| self.cells[cell].iter().copied())
+            .collect::<Vec<_>>();
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+
+    fn cells_of(rect: &Rect) -> impl Iterator<Item = usize> {
+        let (min_x, min_y) = Self::cell_coordinates(rect.min);
+        let (max_x, max_y) = Self::cell_coordinates(rect.max);
+        (min_y..=max_y).flat_map(move |y| (min_x..=max_x).map(move |x| y * CELLS_PER_AXIS + x))
+    }
+
+    fn cell_coordinates(position: Position) -> (usize, usize) {
+        (
+            position.x as usize / CELL_SIZE,
+            position.y as usize / CELL_SIZE,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, query::select, test::RAW_FILE};
+
+    #[test]
+    fn test_spatial_index_matches_linear_scan() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let index = SpatialIndex::new(&sa);
+
+        let region = Rect::new(Position { x: 64, y: 64 }, Position { x: 160, y: 128 });
+        for predicate in [
+            Predicate::intersects(region),
+            Predicate::contained_in(region),
+            Predicate::intersects(region).and(Predicate::hidden(false)),
+        ] {
+            assert_eq!(index.select(&predicate), select(&sa, &predicate));
+        }
+        assert_eq!(
+            index.intersecting(&region),
+            select(&sa, &Predicate::intersects(region))
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use image::{Pixel, Rgba, RgbaImage, imageops};
+
+use crate::core::color::{linear_to_srgb, srgb_to_linear};
+
+/// The space in which layers are blended together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendSpace {
+    /// Blends gamma-encoded sRGB values directly
+    ///
+    /// Semi-transparent layers come out darker than in linear light, but this is
+    /// how every image rendered so far was produced.
+    #[default]
+    Srgb,
+    /// Blends in linear light and converts back to sRGB once every layer is composited
+    Linear,
+}
+
+/// A canvas that layers are composited onto
+pub(crate) trait Composite: Send + Sized {
+    fn new(width: u32, height: u32) -> Self;
+
+    /// Blends `source` over the pixel at (`x`, `y`)
+    fn blend(&mut self, x: u32, y: u32, source: &Rgba<u8>);
+
+    /// Blends every pixel of `other` over this canvas
+    fn overlay(&mut self, other: &Self);
+
+    fn into_image(self) -> RgbaImage;
+}
+
+/// Composites in gamma-encoded sRGB with 8 bits per channel
+pub(crate) struct SrgbCanvas(RgbaImage);
+
+impl Composite for SrgbCanvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self(RgbaImage::new(width, height))
+    }
+
+    fn blend(&mut self, x: u32, y: u32, source: &Rgba<u8>) {
+        self.0.get_pixel_mut(x, y).blend(source);
+    }
+
+    fn overlay(&mut self, other: &Self) {
+        imageops::overlay(&mut self.0, &other.0, 0, 0);
+    }
+
+    fn into_image(self) -> RgbaImage {
+        self.0
+    }
+}
+
+/// Composites premultiplied linear light in floating point
+pub(crate) struct LinearCanvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f32; 4]>,
+}
+
+impl LinearCanvas {
+    fn over(destination: &mut [f32; 4], source: &[f32; 4]) {
+        let remaining = 1.0 - source[3];
+        for (d, s) in destination.iter_mut().zip(source) {
+            *d = s + *d * remaining;
+        }
+    }
+}
+
+impl Composite for LinearCanvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0.0; 4]; (width * height) as usize],
+        }
+    }
+
+    fn blend(&mut self, x: u32, y: u32, source: &Rgba<u8>) {
+        let alpha = source[3] as f32 / 255.0;
+        let source = [
+            srgb_to_linear(source[0]) * alpha,
+            srgb_to_linear(source[1]) * alpha,
+            srgb_to_linear(source[2]) * alpha,
+            alpha,
+        ];
+        let i = (y * self.width + x) as usize;
+        Self::over(&mut self.pixels[i], &source);
+    }
+
+    fn overlay(&mut self, other: &Self) {
+        for (destination, source) in self.pixels.iter_mut().zip(&other.pixels) {
+            Self::over(destination, source);
+        }
+    }
+
+    fn into_image(self) -> RgbaImage {
+        let mut image = RgbaImage::new(self.width, self.height);
+        for (pixel, [r, g, b, a]) in image.pixels_mut().zip(self.pixels) {
+            if a <= 0.0 {
+                continue;
+            }
+            *pixel = Rgba([
+                linear_to_srgb(r / a),
+                linear_to_srgb(g / a),
+                linear_to_srgb(b / a),
+                (a * 255.0).round().clamp(0.0, 255.0) as u8,
+            ]);
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_blend_is_lighter() {
+        let black = Rgba([0, 0, 0, 255]);
+        let white = Rgba([255, 255, 255, 128]);
+
+        let mut srgb = SrgbCanvas::new(1, 1);
+        srgb.blend(0, 0, &black);
+        srgb.blend(0, 0, &white);
+        let mut linear = LinearCanvas::new(1, 1);
+        linear.blend(0, 0, &black);
+        linear.blend(0, 0, &white);
+
+        let srgb = srgb.into_image();
+        let linear = linear.into_image();
+        assert_eq!(linear.get_pixel(0, 0)[3], 255);
+        assert!(linear.get_pixel(0, 0)[0] > srgb.get_pixel(0, 0)[0]);
+    }
+
+    #[test]
+    fn test_linear_opaque_roundtrip() {
+        let color = Rgba([12, 200, 99, 255]);
+        let mut linear = LinearCanvas::new(1, 1);
+        linear.blend(0, 0, &Rgba([255, 0, 0, 255]));
+        linear.blend(0, 0, &color);
+        assert_eq!(*linear.into_image().get_pixel(0, 0), color);
+    }
+}
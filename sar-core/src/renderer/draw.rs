@@ -1,14 +1,19 @@
 use crate::{core::sa::Color, Result};
-use image::{imageops, GenericImage, ImageBuffer, Pixel, Rgba, RgbaImage};
-use imageproc::geometric_transformations::Projection;
-use std::sync::mpsc;
+use image::{
+    imageops, GenericImage, GenericImageView, GrayImage, ImageBuffer, Luma, Rgba, RgbaImage,
+};
+use imageproc::{geometric_transformations::Projection, rect::Rect};
+use std::{collections::BTreeSet, ops::Range, sync::mpsc};
 
 use crate::core::{
     result::SARError,
     sa::{SymbolArt, SymbolArtLayer},
 };
+use crate::layout::Layout;
 use rayon::prelude::*;
 
+use super::blend::{BlendSpace, Composite, LinearCanvas, SrgbCanvas};
+use super::overlay::{self, Outline};
 use super::resource::{self};
 
 /// A trait defining the core rendering capabilities for SymbolArt compositions
@@ -25,7 +30,8 @@ where
 ///
 /// The `SymbolArtDrawer` is responsible for converting SymbolArt compositions into
 /// raster images. It handles the rendering of individual layers, applying transformations,
-/// and compositing them into a final image.
+/// and compositing them into a final image. Arts with an unknown canvas fail to
+/// render with [`SARError::UnsupportedCanvas`].
 ///
 /// # Examples
 ///
@@ -48,6 +54,11 @@ where
 /// - `with_raise_error`: Controls whether rendering errors should be raised or suppressed
 /// - Canvas size: Default is 256x256 pixels
 /// - `with_chunk_size`: Controls parallel processing of layers (default: 10)
+/// - `with_blend_space`: Controls whether layers blend in sRGB (default) or linear light
+/// - `with_layers`: Restricts the drawing to some of the layers (default: all of them)
+/// - `with_show_hidden`: Draws hidden layers as if they were visible
+/// - `with_ghost`: Draws the layers outside the selection faded instead of leaving them out
+/// - `with_overlay`: Draws the whole canvas with the quad of every selected layer outlined
 ///
 /// # Performance
 ///
@@ -69,6 +80,11 @@ pub struct SymbolArtDrawer {
     canvas_size: (u32, u32),
     chunk_size: usize,
     suppress_failure: bool,
+    blend_space: BlendSpace,
+    layers: LayerSelection,
+    show_hidden: bool,
+    ghost: bool,
+    overlay: bool,
 }
 
 impl SymbolArtDrawer {
@@ -81,6 +97,11 @@ impl SymbolArtDrawer {
             canvas_size,
             chunk_size: 10,
             suppress_failure: true,
+            blend_space: BlendSpace::default(),
+            layers: LayerSelection::All,
+            show_hidden: false,
+            ghost: false,
+            overlay: false,
         }
     }
 
@@ -94,6 +115,104 @@ impl SymbolArtDrawer {
         self
     }
 
+    /// Sets the space in which layers are blended together
+    pub fn with_blend_space(mut self, blend_space: BlendSpace) -> Self {
+        self.blend_space = blend_space;
+        self
+    }
+
+    /// Restricts the drawing to the selected layers, keeping their stacking order
+    pub fn with_layers(mut self, layers: LayerSelection) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Sets whether hidden layers are drawn as if they were visible
+    pub fn with_show_hidden(mut self, show_hidden: bool) -> Self {
+        self.show_hidden = show_hidden;
+        self
+    }
+
+    /// Sets whether the layers outside the selection are drawn faded by
+    /// [`GHOST_ALPHA`] instead of being left out
+    pub fn with_ghost(mut self, ghost: bool) -> Self {
+        self.ghost = ghost;
+        self
+    }
+
+    /// Sets whether renders show the whole canvas with a debug overlay
+    ///
+    /// The overlay outlines the quad of every selected layer, hidden ones
+    /// included, and marks its corners and index. Layers that cannot be rendered
+    /// are outlined in red rather than left out. The visible area of the canvas
+    /// is framed and the center lines are drawn, so quads reaching outside it
+    /// show too. See [`overlay`] for the colors.
+    pub fn with_overlay(mut self, overlay: bool) -> Self {
+        self.overlay = overlay;
+        self
+    }
+
+    /// Returns the alpha a layer is drawn with, or `None` when it is not drawn
+    fn layer_alpha<L>(&self, index: usize, layer: &L) -> Option<u8>
+    where
+        L: SymbolArtLayer,
+    {
+        if layer.is_hidden() && !self.show_hidden {
+            return None;
+        }
+        let alpha = layer.color().a;
+        if self.layers.contains(index) {
+            Some(alpha)
+        } else if self.ghost {
+            Some(fade(alpha, GHOST_ALPHA))
+        } else {
+            None
+        }
+    }
+
+    /// Renders a multi-part layout into a single image
+    ///
+    /// `parts` holds the SymbolArt of each part, in the same order as
+    /// [`Layout::parts`]. Every grid cell is as large as the largest rendered part,
+    /// and cells without a part are left transparent.
+    pub fn draw_layout<S, L>(
+        &self,
+        layout: &Layout,
+        parts: &[S],
+        scale: f32,
+    ) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>>
+    where
+        S: SymbolArt<Layer = L>,
+        L: SymbolArtLayer + Sync,
+    {
+        if parts.len() != layout.parts().len() {
+            return Err(SARError::InvalidLayout(format!(
+                "expected {} parts but got {}",
+                layout.parts().len(),
+                parts.len()
+            )));
+        }
+
+        let images = parts
+            .iter()
+            .map(|sa| self.draw_with_scale(sa, scale))
+            .collect::<Result<Vec<_>>>()?;
+        let cell_width = images.iter().map(|image| image.width()).max().unwrap_or(0);
+        let cell_height = images.iter().map(|image| image.height()).max().unwrap_or(0);
+
+        let mut canvas = RgbaImage::new(cell_width * layout.columns(), cell_height * layout.rows());
+        for (part, image) in layout.parts().iter().zip(&images) {
+            imageops::replace(
+                &mut canvas,
+                image,
+                (part.column * cell_width) as i64,
+                (part.row * cell_height) as i64,
+            );
+        }
+
+        Ok(canvas)
+    }
+
     fn calc_canvas_size(&self, scale: f32) -> (u32, u32) {
         (
             (self.canvas_size.0 as f32 * scale) as u32,
@@ -101,14 +220,20 @@ impl SymbolArtDrawer {
         )
     }
 
-    fn calc_view_size<S>(sa: &S, scale: f32) -> (u32, u32)
+    /// Returns the size of the visible area at `scale`
+    ///
+    /// Fails with [`SARError::UnsupportedCanvas`] when the canvas is unknown.
+    fn calc_view_size<S>(sa: &S, scale: f32) -> Result<(u32, u32)>
     where
         S: SymbolArt,
     {
-        (
-            (sa.width() as f32 * scale) as u32,
-            (sa.height() as f32 * scale) as u32,
-        )
+        let canvas = sa
+            .canvas()
+            .ok_or_else(|| SARError::UnsupportedCanvas("unknown canvas size".to_string()))?;
+        Ok((
+            (canvas.width() as f32 * scale) as u32,
+            (canvas.height() as f32 * scale) as u32,
+        ))
     }
 
     fn get_projection<L>(&self, layer: &L, scale: f32) -> Result<Projection>
@@ -141,80 +266,287 @@ impl SymbolArtDrawer {
         Ok(projection)
     }
 
-    fn render_symbol(base: &mut RgbaImage, symbol: &mut RgbaImage, color: RenderColor) {
-        for (x, y, pixel) in base.enumerate_pixels_mut() {
-            let symbol_pixel = symbol.get_pixel(x, y);
-            if symbol_pixel[3] > 0 {
-                match color {
-                    RenderColor::Color(color) => pixel.blend(&color.into()),
-                    RenderColor::None => {
-                        pixel.blend(symbol_pixel);
-                    }
+    /// Counts, for every layer, the view pixels where it is the topmost visible layer
+    ///
+    /// Hidden and fully transparent layers cover nothing. Layers that cannot be
+    /// rendered cover nothing either, unless the drawer raises errors. The layer
+    /// selection and the ghost and hidden layer settings are ignored, so the
+    /// coverage only depends on the art.
+    pub fn coverage<S, L>(&self, sa: &S) -> Result<Vec<usize>>
+    where
+        S: SymbolArt<Layer = L>,
+        L: SymbolArtLayer + Sync,
+    {
+        let masks = self.layer_masks(sa)?;
+        let view_size = Self::calc_view_size(sa, 1.0)?;
+
+        let mut owners = vec![None; (view_size.0 * view_size.1) as usize];
+        for (i, mask) in masks.iter().enumerate() {
+            let Some(mask) = mask else {
+                continue;
+            };
+            for (owner, alpha) in owners.iter_mut().zip(mask.pixels()) {
+                if owner.is_none() && alpha[0] > 0 {
+                    *owner = Some(i);
                 }
             }
         }
+
+        let mut coverage = vec![0; masks.len()];
+        for owner in owners.into_iter().flatten() {
+            coverage[owner] += 1;
+        }
+        Ok(coverage)
     }
-}
 
-enum RenderColor {
-    Color(Color),
-    None,
-}
+    /// Lists the layers covering a pixel of the image rendered at `scale`, topmost first
+    ///
+    /// Every layer is sampled through the same projection the renderer warps it
+    /// with, so a layer is listed exactly when it blends a non-zero alpha into the
+    /// pixel. Layers the drawer does not draw, such as hidden ones, are skipped,
+    /// and so are layers that cannot be rendered, unless the drawer raises errors.
+    /// Nothing is rendered, so picking is cheap enough to follow a cursor.
+    pub fn pick<S, L>(&self, sa: &S, scale: f32, x: u32, y: u32) -> Result<Vec<Pick>>
+    where
+        S: SymbolArt<Layer = L>,
+        L: SymbolArtLayer,
+    {
+        let canvas_size = self.calc_canvas_size(scale);
+        let view_size = Self::calc_view_size(sa, scale)?;
+        if x >= view_size.0 || y >= view_size.1 {
+            return Err(SARError::PositionOutOfRange(x as i32, y as i32));
+        }
+        let point = (
+            (canvas_size.0 / 2 - view_size.0 / 2 + x) as f32,
+            (canvas_size.1 / 2 - view_size.1 / 2 + y) as f32,
+        );
+
+        let mut picks = Vec::new();
+        // The share of the pixel left for the layers below once the ones above blend
+        let mut remaining = 1.0;
+        for (index, layer) in sa.layers().iter().enumerate() {
+            let Some(layer_alpha) = self.layer_alpha(index, layer) else {
+                continue;
+            };
+            let symbol_alpha = match self.sample(layer, scale, point) {
+                Ok(alpha) => alpha,
+                Err(_) if self.suppress_failure => continue,
+                Err(e) => return Err(e),
+            };
+            let alpha = fade(symbol_alpha, layer_alpha);
+            if alpha == 0 {
+                continue;
+            }
+            let opacity = alpha as f32 / 255.0;
+            picks.push(Pick {
+                index,
+                alpha,
+                contribution: remaining * opacity,
+            });
+            remaining *= 1.0 - opacity;
+        }
+        Ok(picks)
+    }
 
-impl Default for SymbolArtDrawer {
-    fn default() -> Self {
-        Self {
-            resource: resource::Resource::new().unwrap(),
-            canvas_size: (256, 256),
-            chunk_size: 10,
-            suppress_failure: true,
+    /// Draws the debug overlay of the selected layers over a rendered canvas
+    fn draw_overlay<S, L>(&self, sa: &S, canvas: &mut RgbaImage, view_size: (u32, u32), scale: f32)
+    where
+        S: SymbolArt<Layer = L>,
+        L: SymbolArtLayer,
+    {
+        let canvas_size = self.calc_canvas_size(scale);
+        let view = Rect::at(
+            (canvas_size.0 / 2 - view_size.0 / 2) as i32,
+            (canvas_size.1 / 2 - view_size.1 / 2) as i32,
+        )
+        .of_size(view_size.0.max(1), view_size.1.max(1));
+
+        let layers = sa.layers();
+        let outlines = layers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.layers.contains(*i))
+            .map(|(i, layer)| {
+                let renders = self.resource.get_image(layer.symbol().id()).is_some()
+                    && self.get_projection(layer, scale).is_ok();
+                let outline = if !renders {
+                    Outline::Failed
+                } else if layer.is_hidden() {
+                    Outline::Hidden
+                } else {
+                    Outline::Visible
+                };
+                (i, layer, outline)
+            })
+            .collect::<Vec<_>>();
+        overlay::draw(canvas, view, &outlines, scale);
+    }
+
+    /// Returns the symbol alpha a layer warps onto a point of the canvas
+    ///
+    /// The point is mapped back and looked up exactly as [`Self::rasterize`] does
+    /// through `warp_into` with nearest neighbour interpolation.
+    fn sample<L>(&self, layer: &L, scale: f32, point: (f32, f32)) -> Result<u8>
+    where
+        L: SymbolArtLayer,
+    {
+        let image = self
+            .resource
+            .get_image(layer.symbol().id())
+            .ok_or(SARError::SymbolNotFound(layer.symbol().id()))?;
+        let projection = self.get_projection(layer, scale)?;
+
+        let (x, y) = projection.invert() * point;
+        if x < -0.5 || y < -0.5 {
+            return Ok(0);
         }
+        let (x, y) = ((x + 0.5) as u32, (y + 0.5) as u32);
+        let symbol = image.inner();
+        if x >= symbol.width() || y >= symbol.height() {
+            return Ok(0);
+        }
+        Ok(symbol.get_pixel(x, y)[3])
     }
-}
 
-impl<S, L> Drawer<S, L> for SymbolArtDrawer
-where
-    S: SymbolArt<Layer = L>,
-    L: SymbolArtLayer + Sync,
-{
-    fn draw(&self, sa: &S) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        self.draw_with_scale(sa, 1.0)
+    /// Renders, for every layer, the alpha it blends into each view pixel
+    ///
+    /// The alpha is the symbol's own alpha times the layer alpha, exactly as
+    /// composited by [`Drawer::draw`] with the default layer settings. Hidden
+    /// layers have no mask, and neither do layers that cannot be rendered, unless
+    /// the drawer raises errors.
+    ///
+    /// The masks describe the art itself, for analysis: the layer selection and
+    /// the ghost and hidden layer settings are ignored, so the result does not
+    /// depend on how the drawer is configured for display, and layer indices stay
+    /// those of the art.
+    pub(crate) fn layer_masks<S, L>(&self, sa: &S) -> Result<Vec<Option<GrayImage>>>
+    where
+        S: SymbolArt<Layer = L>,
+        L: SymbolArtLayer + Sync,
+    {
+        let canvas_size = self.calc_canvas_size(1.0);
+        let view_size = Self::calc_view_size(sa, 1.0)?;
+        let origin = (
+            canvas_size.0 / 2 - view_size.0 / 2,
+            canvas_size.1 / 2 - view_size.1 / 2,
+        );
+
+        sa.layers()
+            .par_iter()
+            .map(|layer| {
+                if layer.is_hidden() {
+                    return Ok(None);
+                }
+                let alpha = layer.color().a;
+                if alpha == 0 {
+                    return Ok(Some(GrayImage::new(view_size.0, view_size.1)));
+                }
+                let (_, symbol) = match self.rasterize(layer, 1.0, canvas_size) {
+                    Ok(rasterized) => rasterized,
+                    Err(_) if self.suppress_failure => return Ok(None),
+                    Err(e) => return Err(e),
+                };
+
+                Ok(Some(GrayImage::from_fn(
+                    view_size.0,
+                    view_size.1,
+                    |x, y| {
+                        let symbol_pixel = symbol.get_pixel(origin.0 + x, origin.1 + y);
+                        Luma([fade(symbol_pixel[3], alpha)])
+                    },
+                )))
+            })
+            .collect()
     }
 
-    fn draw_with_scale(&self, sa: &S, scale: f32) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    /// Warps the symbol of a layer onto a transparent canvas
+    fn rasterize<L>(
+        &self,
+        layer: &L,
+        scale: f32,
+        canvas_size: (u32, u32),
+    ) -> Result<(resource::Image<'_>, RgbaImage)>
+    where
+        L: SymbolArtLayer,
+    {
+        let image = self
+            .resource
+            .get_image(layer.symbol().id())
+            .ok_or(SARError::SymbolNotFound(layer.symbol().id()))?;
+        let projection = self.get_projection(layer, scale)?;
+
+        let mut symbol = RgbaImage::new(canvas_size.0, canvas_size.1);
+        imageproc::geometric_transformations::warp_into(
+            &image.inner().to_image(),
+            &projection,
+            imageproc::geometric_transformations::Interpolation::Nearest,
+            image::Rgba([0; 4]),
+            &mut symbol,
+        );
+
+        Ok((image, symbol))
+    }
+
+    /// Blends a warped symbol onto the canvas
+    ///
+    /// Every pixel is blended with a coverage equal to the symbol's own alpha times
+    /// the layer alpha, so the soft edges of the atlas stay soft.
+    fn render_symbol<C>(base: &mut C, symbol: &RgbaImage, color: RenderColor)
+    where
+        C: Composite,
+    {
+        for (x, y, symbol_pixel) in symbol.enumerate_pixels() {
+            if symbol_pixel[3] == 0 {
+                continue;
+            }
+            let source = match color {
+                RenderColor::Color(color) => {
+                    Rgba([color.r, color.g, color.b, fade(symbol_pixel[3], color.a)])
+                }
+                RenderColor::Own(alpha) => Rgba([
+                    symbol_pixel[0],
+                    symbol_pixel[1],
+                    symbol_pixel[2],
+                    fade(symbol_pixel[3], alpha),
+                ]),
+            };
+            base.blend(x, y, &source);
+        }
+    }
+
+    /// Composites every visible layer onto a canvas of type `C`
+    fn composite<C, S, L>(&self, sa: &S, scale: f32) -> Result<RgbaImage>
+    where
+        C: Composite,
+        S: SymbolArt<Layer = L>,
+        L: SymbolArtLayer + Sync,
+    {
         let canvas_size = self.calc_canvas_size(scale);
-        let mut canvas = RgbaImage::from_pixel(canvas_size.0, canvas_size.1, image::Rgba([0; 4]));
+        let mut canvas = C::new(canvas_size.0, canvas_size.1);
 
-        let (tx, rx) = mpsc::channel();
-        let mut overlays = sa
+        // Layers that are not drawn stay in their chunk so the chunks do not shift
+        let layers = sa
             .layers()
+            .into_iter()
+            .enumerate()
+            .map(|(i, layer)| (self.layer_alpha(i, &layer), layer))
+            .collect::<Vec<_>>();
+
+        let (tx, rx) = mpsc::channel();
+        let mut overlays = layers
             .par_chunks(self.chunk_size)
             .rev()
             .enumerate()
             .filter_map(|(i, chunk)| {
                 let tx = tx.clone();
-                let mut canvas = RgbaImage::new(canvas_size.0, canvas_size.1);
-                for layer in chunk.iter().rev() {
-                    if layer.is_hidden() {
+                let mut canvas = C::new(canvas_size.0, canvas_size.1);
+                for (alpha, layer) in chunk.iter().rev() {
+                    let Some(alpha) = *alpha else {
                         continue;
-                    }
-
-                    let image = match self.resource.get_image(layer.symbol().id()) {
-                        Some(image) => image,
-                        None => {
-                            if self.suppress_failure {
-                                continue;
-                            }
-
-                            tx.send(SARError::SymbolNotFound(layer.symbol().id()))
-                                .unwrap();
-                            return None;
-                        }
                     };
 
-                    let mut symbol = RgbaImage::new(canvas_size.0, canvas_size.1);
-                    let projection = match self.get_projection(layer, scale) {
-                        Ok(projection) => projection,
+                    let (image, symbol) = match self.rasterize(layer, scale, canvas_size) {
+                        Ok(rasterized) => rasterized,
                         Err(e) => {
                             if self.suppress_failure {
                                 continue;
@@ -225,21 +557,20 @@ where
                         }
                     };
 
-                    imageproc::geometric_transformations::warp_into(
-                        &image.inner().to_image(),
-                        &projection,
-                        imageproc::geometric_transformations::Interpolation::Nearest,
-                        image::Rgba([0; 4]),
-                        &mut symbol,
-                    );
-
                     if let resource::Image::Color(_) = image {
-                        SymbolArtDrawer::render_symbol(&mut canvas, &mut symbol, RenderColor::None);
+                        SymbolArtDrawer::render_symbol(
+                            &mut canvas,
+                            &symbol,
+                            RenderColor::Own(alpha),
+                        );
                     } else {
                         SymbolArtDrawer::render_symbol(
                             &mut canvas,
-                            &mut symbol,
-                            RenderColor::Color(layer.color()),
+                            &symbol,
+                            RenderColor::Color(Color {
+                                a: alpha,
+                                ..layer.color()
+                            }),
                         );
                     }
                 }
@@ -255,10 +586,106 @@ where
 
         overlays.sort_by_key(|(i, _)| *i);
         for (_, overlay) in overlays {
-            imageops::overlay(&mut canvas, &overlay, 0, 0);
+            canvas.overlay(&overlay);
+        }
+
+        Ok(canvas.into_image())
+    }
+}
+
+/// The alpha, out of 255, that layers outside the selection are faded by in ghost mode
+pub const GHOST_ALPHA: u8 = 64;
+
+/// Which layers a drawer draws, by their index counted from the top
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum LayerSelection {
+    /// Every layer
+    #[default]
+    All,
+    /// The layers whose index falls within the range
+    Range(Range<usize>),
+    /// The layers at the given indices
+    Set(BTreeSet<usize>),
+}
+
+impl LayerSelection {
+    /// Selects a single layer
+    pub fn solo(index: usize) -> Self {
+        Self::Set(BTreeSet::from([index]))
+    }
+
+    /// Returns whether the layer at `index` is selected
+    pub fn contains(&self, index: usize) -> bool {
+        match self {
+            LayerSelection::All => true,
+            LayerSelection::Range(range) => range.contains(&index),
+            LayerSelection::Set(indices) => indices.contains(&index),
+        }
+    }
+}
+
+/// A layer covering a picked pixel, as returned by [`SymbolArtDrawer::pick`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pick {
+    /// The index of the layer, counted from the top
+    pub index: usize,
+    /// The alpha the layer blends into the pixel: its symbol alpha times its own
+    pub alpha: u8,
+    /// The share of the rendered pixel the layer accounts for once the layers
+    /// above it are blended over it, between 0 and 1
+    pub contribution: f32,
+}
+
+enum RenderColor {
+    /// Paints the symbol mask with the layer color
+    Color(Color),
+    /// Keeps the symbol's own colors, faded by the layer alpha
+    Own(u8),
+}
+
+/// Multiplies two alpha values between 0 and 255
+fn fade(a: u8, b: u8) -> u8 {
+    ((a as u16 * b as u16 + 127) / 255) as u8
+}
+
+impl Default for SymbolArtDrawer {
+    fn default() -> Self {
+        Self {
+            resource: resource::Resource::new().unwrap(),
+            canvas_size: (256, 256),
+            chunk_size: 10,
+            suppress_failure: true,
+            blend_space: BlendSpace::default(),
+            layers: LayerSelection::All,
+            show_hidden: false,
+            ghost: false,
+            overlay: false,
+        }
+    }
+}
+
+impl<S, L> Drawer<S, L> for SymbolArtDrawer
+where
+    S: SymbolArt<Layer = L>,
+    L: SymbolArtLayer + Sync,
+{
+    fn draw(&self, sa: &S) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.draw_with_scale(sa, 1.0)
+    }
+
+    fn draw_with_scale(&self, sa: &S, scale: f32) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        let canvas_size = self.calc_canvas_size(scale);
+        let view_size = Self::calc_view_size(sa, scale)?;
+        let mut canvas = match self.blend_space {
+            BlendSpace::Srgb => self.composite::<SrgbCanvas, _, _>(sa, scale)?,
+            BlendSpace::Linear => self.composite::<LinearCanvas, _, _>(sa, scale)?,
+        };
+
+        if self.overlay {
+            self.draw_overlay(sa, &mut canvas, view_size, scale);
+            return Ok(canvas);
         }
 
-        let view_size = Self::calc_view_size(sa, scale);
         Ok(canvas
             .sub_image(
                 canvas_size.0 / 2 - view_size.0 / 2,
@@ -275,7 +702,7 @@ mod tests {
     use image::codecs::png::PngEncoder;
 
     use super::*;
-    use crate::{parse, test::RAW_FILE};
+    use crate::{Layer, Position, SymbolId, parse, test::RAW_FILE};
 
     #[test]
     fn test_drawer() {
@@ -293,6 +720,33 @@ mod tests {
         assert_eq!(buff.len(), include_bytes!("fixture/test.png").len());
     }
 
+    #[test]
+    fn test_drawer_linear_blend() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+
+        let srgb = SymbolArtDrawer::new().draw(&sa).unwrap();
+        let linear = SymbolArtDrawer::new()
+            .with_blend_space(BlendSpace::Linear)
+            .draw(&sa)
+            .unwrap();
+        assert_eq!(srgb.dimensions(), linear.dimensions());
+        assert_ne!(srgb, linear);
+    }
+
+    #[test]
+    fn test_render_symbol_uses_mask_alpha() {
+        let symbol = RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 128]));
+
+        let mut canvas = SrgbCanvas::new(1, 1);
+        let color = Color::new(255, 255, 0, 0);
+        SymbolArtDrawer::render_symbol(&mut canvas, &symbol, RenderColor::Color(color));
+        assert_eq!(*canvas.into_image().get_pixel(0, 0), Rgba([255, 0, 0, 128]));
+
+        let mut canvas = SrgbCanvas::new(1, 1);
+        SymbolArtDrawer::render_symbol(&mut canvas, &symbol, RenderColor::Own(128));
+        assert_eq!(canvas.into_image().get_pixel(0, 0)[3], 64);
+    }
+
     #[test]
     fn test_drawer_with_scale() {
         let bytes = Vec::from(RAW_FILE);
@@ -308,4 +762,204 @@ mod tests {
             .unwrap();
         assert_eq!(buff.len(), include_bytes!("fixture/testx2.png").len());
     }
+
+    #[test]
+    fn test_draw_layer_selection() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let truncated = |range: Range<usize>| {
+            let mut part = sa.clone();
+            *part.layers_mut() = part.layers_mut()[range].to_vec();
+            part
+        };
+
+        let drawer = SymbolArtDrawer::new().with_layers(LayerSelection::Range(0..25));
+        assert_eq!(
+            drawer.draw(&sa).unwrap(),
+            SymbolArtDrawer::new().draw(&truncated(0..25)).unwrap()
+        );
+
+        // A single layer is drawn alone, whatever chunk it was in
+        let drawer = SymbolArtDrawer::new().with_layers(LayerSelection::solo(12));
+        assert_eq!(
+            drawer.draw(&sa).unwrap(),
+            SymbolArtDrawer::new().draw(&truncated(12..13)).unwrap()
+        );
+
+        let selection = LayerSelection::Set(BTreeSet::from([3, 12]));
+        assert!(selection.contains(3) && !selection.contains(4));
+        let selected = SymbolArtDrawer::new().with_layers(selection.clone());
+        let ghosted = SymbolArtDrawer::new()
+            .with_layers(selection)
+            .with_ghost(true);
+        let full = SymbolArtDrawer::new().draw(&sa).unwrap();
+        let ghost = ghosted.draw(&sa).unwrap();
+        assert_ne!(ghost, full);
+        assert_ne!(ghost, selected.draw(&sa).unwrap());
+        assert_eq!(
+            SymbolArtDrawer::new().with_ghost(true).draw(&sa).unwrap(),
+            full
+        );
+    }
+
+    #[test]
+    fn test_draw_show_hidden() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let mut hidden = sa.clone();
+        hidden.layers_mut()[0].set_hidden(true);
+
+        let drawer = SymbolArtDrawer::new();
+        let full = drawer.draw(&sa).unwrap();
+        assert_ne!(drawer.draw(&hidden).unwrap(), full);
+        let drawer = drawer.with_show_hidden(true);
+        assert_eq!(drawer.draw(&hidden).unwrap(), full);
+        assert_eq!(
+            drawer.pick(&hidden, 1.0, 96, 48).unwrap(),
+            drawer.pick(&sa, 1.0, 96, 48).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_draw_overlay() {
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let p = |x, y| Position { x, y };
+        let mut visible = Layer::new(SymbolId::new(731));
+        visible.set_vertices([p(96, 96), p(96, 160), p(160, 96), p(160, 160)]);
+        let mut hidden = visible;
+        hidden.set_vertices([p(40, 100), p(40, 140), p(80, 100), p(80, 140)]);
+        hidden.set_hidden(true);
+        let mut failed = visible;
+        failed.set_vertices([p(180, 100), p(180, 140), p(220, 100), p(220, 140)]);
+        failed.set_symbol(SymbolId::new(0));
+        let layers = sa.layers_mut();
+        layers.clear();
+        layers.extend([visible, hidden, failed]);
+
+        let image = SymbolArtDrawer::new().with_overlay(true).draw(&sa).unwrap();
+        assert_eq!(image.dimensions(), (256, 256));
+        assert_eq!(image.get_pixel(128, 10), &overlay::CENTER);
+        assert_eq!(image.get_pixel(32, 150), &overlay::VIEW);
+        assert_eq!(image.get_pixel(110, 96), &overlay::VISIBLE);
+        assert_eq!(image.get_pixel(50, 100), &overlay::HIDDEN);
+        assert_eq!(image.get_pixel(190, 100), &overlay::FAILED);
+        assert_eq!(image.get_pixel(96, 96), &overlay::CORNERS[0]);
+        assert_eq!(image.get_pixel(160, 160), &overlay::CORNERS[3]);
+        // The top of the 0 labelling the first layer, centered on its quad
+        assert_eq!(image.get_pixel(127, 126), &Rgba([255, 255, 255, 255]));
+
+        let drawer = SymbolArtDrawer::new()
+            .with_overlay(true)
+            .with_layers(LayerSelection::solo(0));
+        let image = drawer.draw_with_scale(&sa, 2.0).unwrap();
+        assert_eq!(image.dimensions(), (512, 512));
+        assert_ne!(image.get_pixel(380, 200), &overlay::FAILED);
+    }
+
+    #[test]
+    fn test_pick_matches_layer_masks() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let drawer = SymbolArtDrawer::new();
+        let masks = drawer.layer_masks(&sa).unwrap();
+
+        let pixels = (0..193)
+            .step_by(19)
+            .flat_map(|x| (0..96).step_by(9).map(move |y| (x, y)));
+        for (x, y) in pixels {
+            let picks = drawer.pick(&sa, 1.0, x, y).unwrap();
+            let expected = masks
+                .iter()
+                .enumerate()
+                .filter_map(|(i, mask)| Some((i, mask.as_ref()?.get_pixel(x, y)[0])))
+                .filter(|(_, alpha)| *alpha > 0)
+                .collect::<Vec<_>>();
+            let actual = picks.iter().map(|p| (p.index, p.alpha)).collect::<Vec<_>>();
+            assert_eq!(actual, expected);
+            assert!(picks.iter().map(|p| p.contribution).sum::<f32>() <= 1.0 + 1e-4);
+        }
+        assert!(drawer.pick(&sa, 1.0, 193, 0).is_err());
+    }
+
+    #[test]
+    fn test_coverage_ignores_layer_settings() {
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        sa.layers_mut()[5].set_hidden(true);
+        let coverage = SymbolArtDrawer::new().coverage(&sa).unwrap();
+        assert_eq!(coverage[5], 0);
+
+        let configured = SymbolArtDrawer::new()
+            .with_layers(LayerSelection::Range(10..20))
+            .with_ghost(true)
+            .with_show_hidden(true);
+        assert_eq!(configured.coverage(&sa).unwrap(), coverage);
+    }
+
+    #[test]
+    fn test_pick_with_scale() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let drawer = SymbolArtDrawer::new();
+        let image = drawer.draw_with_scale(&sa, 2.0).unwrap();
+
+        let pixels = (0..386)
+            .step_by(37)
+            .flat_map(|x| (0..192).step_by(17).map(move |y| (x, y)));
+        for (x, y) in pixels {
+            let picks = drawer.pick(&sa, 2.0, x, y).unwrap();
+            assert_eq!(picks.is_empty(), image.get_pixel(x, y)[3] == 0);
+        }
+    }
+
+    #[test]
+    fn test_pick_order_and_hidden() {
+        let mut sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let p = |x, y| Position { x, y };
+        let mut disc = Layer::new(SymbolId::new(731));
+        disc.set_vertices([p(96, 96), p(96, 160), p(160, 96), p(160, 160)]);
+        let mut hidden = disc;
+        hidden.set_hidden(true);
+        let layers = sa.layers_mut();
+        layers.clear();
+        layers.extend([hidden, disc, disc]);
+
+        let drawer = SymbolArtDrawer::new();
+        let picks = drawer.pick(&sa, 1.0, 128 - 32, 128 - 80).unwrap();
+        assert_eq!(
+            picks,
+            vec![
+                Pick {
+                    index: 1,
+                    alpha: 255,
+                    contribution: 1.0
+                },
+                Pick {
+                    index: 2,
+                    alpha: 255,
+                    contribution: 0.0
+                },
+            ]
+        );
+        assert!(drawer.pick(&sa, 1.0, 0, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_draw_layout() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let layout = Layout::new(2, 1)
+            .with_part(0, 0, "left.sar")
+            .unwrap()
+            .with_part(1, 0, "right.sar")
+            .unwrap();
+
+        let drawer = SymbolArtDrawer::default();
+        let single = drawer.draw(&sa).unwrap();
+        let image = drawer
+            .draw_layout(&layout, &[sa.clone(), sa.clone()], 1.0)
+            .unwrap();
+        assert_eq!(image.width(), single.width() * 2);
+        assert_eq!(image.height(), single.height());
+        assert_eq!(
+            image.get_pixel(single.width() + 10, 10),
+            single.get_pixel(10, 10)
+        );
+
+        assert!(drawer.draw_layout(&layout, &[sa], 1.0).is_err());
+    }
 }
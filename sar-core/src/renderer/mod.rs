@@ -1,6 +1,9 @@
+mod blend;
 pub mod draw;
+pub mod overlay;
 pub mod resource;
-pub use draw::SymbolArtDrawer;
+pub use blend::BlendSpace;
+pub use draw::{LayerSelection, Pick, SymbolArtDrawer};
 
 pub(crate) mod default {
     use super::draw::{Drawer, SymbolArtDrawer};
@@ -0,0 +1,153 @@
+use image::{Rgba, RgbaImage};
+use imageproc::{
+    drawing::{Blend, draw_filled_rect_mut, draw_hollow_rect_mut, draw_line_segment_mut},
+    rect::Rect,
+};
+
+use crate::core::sa::SymbolArtLayer;
+
+/// The outline of a visible layer
+pub const VISIBLE: Rgba<u8> = Rgba([0, 200, 255, 255]);
+/// The outline of a hidden layer
+pub const HIDDEN: Rgba<u8> = Rgba([160, 160, 160, 255]);
+/// The outline of a layer that cannot be rendered, such as a quad without a projection
+pub const FAILED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+/// The rectangle around the visible area of the canvas
+pub const VIEW: Rgba<u8> = Rgba([255, 200, 0, 255]);
+/// The lines through the center of the canvas
+pub const CENTER: Rgba<u8> = Rgba([255, 0, 255, 255]);
+/// The marks on the corners of a quad, in the order the file stores them:
+/// top-left, bottom-left, top-right and bottom-right
+pub const CORNERS: [Rgba<u8>; 4] = [
+    Rgba([0, 220, 0, 255]),
+    Rgba([0, 90, 255, 255]),
+    Rgba([255, 140, 0, 255]),
+    Rgba([200, 0, 255, 255]),
+];
+
+/// The backdrop behind layer labels
+const LABEL_BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 160]);
+const LABEL_TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// The digits 0 to 9 as 3x5 bitmaps, one row of 3 bits after another
+const DIGITS: [u16; 10] = [
+    0b111_101_101_101_111,
+    0b010_110_010_010_111,
+    0b111_001_111_100_111,
+    0b111_001_111_001_111,
+    0b101_101_111_001_001,
+    0b111_100_111_001_111,
+    0b111_100_111_101_111,
+    0b111_001_001_001_001,
+    0b111_101_111_101_111,
+    0b111_101_111_001_111,
+];
+
+/// How a layer is outlined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outline {
+    Visible,
+    Hidden,
+    Failed,
+}
+
+impl Outline {
+    fn color(&self) -> Rgba<u8> {
+        match self {
+            Outline::Visible => VISIBLE,
+            Outline::Hidden => HIDDEN,
+            Outline::Failed => FAILED,
+        }
+    }
+}
+
+/// Draws the canvas guides and the quad of every layer over a rendered canvas
+///
+/// `view` is the visible area of the canvas at the render scale, and `layers`
+/// holds the index and outline of each layer to draw, ordered from the top.
+pub(crate) fn draw<L>(
+    canvas: &mut RgbaImage,
+    view: Rect,
+    layers: &[(usize, &L, Outline)],
+    scale: f32,
+) where
+    L: SymbolArtLayer,
+{
+    let (width, height) = (canvas.width() as f32, canvas.height() as f32);
+    draw_line_segment_mut(canvas, (width / 2.0, 0.0), (width / 2.0, height), CENTER);
+    draw_line_segment_mut(canvas, (0.0, height / 2.0), (width, height / 2.0), CENTER);
+    draw_hollow_rect_mut(canvas, view, VIEW);
+
+    // Bottom layers first, so the outlines of the layers above stay on top
+    let mark = (scale.round() as u32).max(1);
+    for (_, layer, outline) in layers.iter().rev() {
+        let corners = corners(*layer, scale);
+        let [top_left, bottom_left, top_right, bottom_right] = corners;
+        for (start, end) in [
+            (top_left, top_right),
+            (top_right, bottom_right),
+            (bottom_right, bottom_left),
+            (bottom_left, top_left),
+        ] {
+            draw_line_segment_mut(canvas, start, end, outline.color());
+        }
+        for ((x, y), color) in corners.into_iter().zip(CORNERS) {
+            let rect = Rect::at(x as i32 - mark as i32, y as i32 - mark as i32)
+                .of_size(mark * 2 + 1, mark * 2 + 1);
+            draw_filled_rect_mut(canvas, rect, color);
+        }
+    }
+
+    for (index, layer, _) in layers.iter().rev() {
+        let center = corners(*layer, scale)
+            .into_iter()
+            .fold((0.0, 0.0), |(x, y), (cx, cy)| (x + cx / 4.0, y + cy / 4.0));
+        draw_label(canvas, *index, center, mark);
+    }
+}
+
+/// Returns the corners of a layer on the canvas, in the order the file stores them
+fn corners<L>(layer: &L, scale: f32) -> [(f32, f32); 4]
+where
+    L: SymbolArtLayer,
+{
+    [
+        layer.top_left(),
+        layer.bottom_left(),
+        layer.top_right(),
+        layer.bottom_right(),
+    ]
+    .map(|position| (position.x as f32 * scale, position.y as f32 * scale))
+}
+
+/// Draws a layer index centered on a point over a translucent backdrop
+fn draw_label(canvas: &mut RgbaImage, index: usize, center: (f32, f32), size: u32) {
+    let digits = index
+        .to_string()
+        .bytes()
+        .map(|digit| DIGITS[(digit - b'0') as usize])
+        .collect::<Vec<_>>();
+    let width = (digits.len() as u32 * 4 + 1) * size;
+    let height = 7 * size;
+    let left = center.0 as i32 - width as i32 / 2;
+    let top = center.1 as i32 - height as i32 / 2;
+
+    let mut blend = Blend(std::mem::take(canvas));
+    draw_filled_rect_mut(
+        &mut blend,
+        Rect::at(left, top).of_size(width, height),
+        LABEL_BACKGROUND,
+    );
+    *canvas = blend.0;
+
+    for (i, glyph) in digits.iter().enumerate() {
+        for bit in 0..15 {
+            if glyph & (1 << (14 - bit)) == 0 {
+                continue;
+            }
+            let x = left + ((i as u32 * 4 + 1 + bit % 3) * size) as i32;
+            let y = top + ((1 + bit / 3) * size) as i32;
+            draw_filled_rect_mut(canvas, Rect::at(x, y).of_size(size, size), LABEL_TEXT);
+        }
+    }
+}
@@ -52,7 +52,12 @@ impl Resource {
         })
     }
 
-    pub(crate) fn get_image(&self, id: SymbolId) -> Option<Image> {
+    /// Returns whether the symbol sheets hold an image for the symbol
+    pub fn contains(id: SymbolId) -> bool {
+        ImageIndex::get(id).is_some()
+    }
+
+    pub(crate) fn get_image(&self, id: SymbolId) -> Option<Image<'_>> {
         let index = ImageIndex::get(id)?;
         let sheet = self.sheets.get(&index.sheet)?;
         let (x, y) = Self::get_coordinates(&index);
@@ -162,6 +167,14 @@ mod test {
         assert!(index.is_some());
     }
 
+    #[test]
+    fn test_contains() {
+        assert!(Resource::contains(SymbolId::new(1)));
+        assert!(Resource::contains(SymbolId::new(768)));
+        assert!(!Resource::contains(SymbolId::new(0)));
+        assert!(!Resource::contains(SymbolId::new(100)));
+    }
+
     #[test]
     fn test_get_image() {
         let resource = Resource::new().unwrap();
@@ -0,0 +1,192 @@
+//! Aggregates statistics over a corpus of SAR files
+//!
+//! [`CorpusStats`] counts how often symbols, layer counts, canvases, colors,
+//! alphas and sound effects occur across many files, along with the files that
+//! fail to parse or render, grouped by [`SARError::kind`](crate::SARError::kind).
+//!
+//! ```
+//! use sar_core::stats::CorpusStats;
+//!
+//! let bytes = include_bytes!("../../fixture/sa0a1d081b8a108bb8c9847c4cd83db662.sar");
+//! let mut stats = CorpusStats::new();
+//! stats.add_file(*bytes, None);
+//! stats.add_file(*b"not a symbol art", None);
+//! assert_eq!(stats.files, 2);
+//! assert_eq!(stats.parse_failures["invalid-file-header"], 1);
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::core::{
+    sa::{Canvas, SymbolArt, SymbolArtLayer},
+    symbol::SymbolId,
+};
+use crate::parser::payload::{Payload, parse};
+use crate::renderer::draw::{Drawer, SymbolArtDrawer};
+
+/// Counts aggregated over every file added
+///
+/// Layer counts include hidden layers. Counts of colors, alphas and symbols are
+/// per layer, the others per file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CorpusStats {
+    /// The number of files added, whether they parse or not
+    pub files: usize,
+    /// The number of layers using each symbol
+    pub symbols: BTreeMap<SymbolId, usize>,
+    /// The number of arts with each layer count
+    pub layer_counts: BTreeMap<usize, usize>,
+    /// The number of arts on each canvas: standard, team-flag or unknown
+    pub canvases: BTreeMap<&'static str, usize>,
+    /// The number of layers of each color, as displayed in `rrggbb` hex notation
+    pub colors: BTreeMap<String, usize>,
+    /// The number of layers at each of the 8 alpha levels stored in the file
+    pub alphas: [usize; 8],
+    /// The number of arts with each sound effect
+    pub sound_effects: BTreeMap<u8, usize>,
+    /// The number of files that fail to parse, by kind of error
+    pub parse_failures: BTreeMap<&'static str, usize>,
+    /// The number of parsed arts that fail to render, by kind of error
+    pub render_failures: BTreeMap<&'static str, usize>,
+}
+
+impl CorpusStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of files that parsed
+    pub fn parsed(&self) -> usize {
+        self.files - self.parse_failures.values().sum::<usize>()
+    }
+
+    /// Parses a file and adds it, or records why it fails to parse
+    ///
+    /// When a `drawer` is given, the art is also rendered and any error is
+    /// recorded. The drawer should be built `with_raise_error(true)`, since a
+    /// drawer that suppresses errors never fails.
+    pub fn add_file(&mut self, bytes: impl Into<Box<[u8]>>, drawer: Option<&SymbolArtDrawer>) {
+        match parse(bytes) {
+            Ok(sa) => {
+                if let Some(Err(e)) = drawer.map(|drawer| drawer.draw(&sa)) {
+                    *self.render_failures.entry(e.kind()).or_default() += 1;
+                }
+                self.add(&sa);
+            }
+            Err(e) => {
+                self.files += 1;
+                *self.parse_failures.entry(e.kind()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Adds a parsed art
+    pub fn add(&mut self, sa: &Payload) {
+        self.files += 1;
+        let layers = sa.layers();
+        *self.layer_counts.entry(layers.len()).or_default() += 1;
+        let canvas = match sa.canvas() {
+            Some(Canvas::Standard) => "standard",
+            Some(Canvas::TeamFlag) => "team-flag",
+            None => "unknown",
+        };
+        *self.canvases.entry(canvas).or_default() += 1;
+        *self.sound_effects.entry(sa.sound_effect()).or_default() += 1;
+
+        for layer in &layers {
+            *self.symbols.entry(layer.symbol().id()).or_default() += 1;
+            *self.colors.entry(layer.color().to_hex()).or_default() += 1;
+            self.alphas[(layer.raw_color().a & 0x07) as usize] += 1;
+        }
+    }
+
+    /// Adds the counts of another corpus, such as one collected in parallel
+    pub fn merge(&mut self, other: CorpusStats) {
+        self.files += other.files;
+        merge_counts(&mut self.symbols, other.symbols);
+        merge_counts(&mut self.layer_counts, other.layer_counts);
+        merge_counts(&mut self.canvases, other.canvases);
+        merge_counts(&mut self.colors, other.colors);
+        for (count, other) in self.alphas.iter_mut().zip(other.alphas) {
+            *count += other;
+        }
+        merge_counts(&mut self.sound_effects, other.sound_effects);
+        merge_counts(&mut self.parse_failures, other.parse_failures);
+        merge_counts(&mut self.render_failures, other.render_failures);
+    }
+}
+
+fn merge_counts<K: Ord>(counts: &mut BTreeMap<K, usize>, other: BTreeMap<K, usize>) {
+    for (key, count) in other {
+        *counts.entry(key).or_default() += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Layer,
+        test::{RAW_FILE, RAW_FILE_UNCOMPRESSED},
+    };
+
+    #[test]
+    fn test_add() {
+        let sa = parse(Vec::from(RAW_FILE)).unwrap();
+        let layers = sa.layers();
+        let mut stats = CorpusStats::new();
+        stats.add(&sa);
+
+        assert_eq!(stats.files, 1);
+        assert_eq!(stats.parsed(), 1);
+        assert_eq!(stats.layer_counts[&layers.len()], 1);
+        assert_eq!(stats.canvases["standard"], 1);
+        assert_eq!(stats.sound_effects[&sa.sound_effect()], 1);
+        assert_eq!(stats.symbols.values().sum::<usize>(), layers.len());
+        assert_eq!(stats.colors.values().sum::<usize>(), layers.len());
+        assert_eq!(stats.alphas.iter().sum::<usize>(), layers.len());
+    }
+
+    #[test]
+    fn test_failures_and_merge() {
+        let drawer = SymbolArtDrawer::new().with_raise_error(true);
+        let mut broken = parse(Vec::from(RAW_FILE)).unwrap();
+        broken.layers_mut()[0] = Layer::new(SymbolId::new(u16::MAX as u32));
+
+        let mut stats = CorpusStats::new();
+        stats.add_file(RAW_FILE, Some(&drawer));
+        stats.add_file(&b"SAR"[..], None);
+        assert!(stats.render_failures.is_empty());
+        assert_eq!(stats.parse_failures["invalid-file-header"], 1);
+
+        let mut other = CorpusStats::new();
+        other.add_file(RAW_FILE_UNCOMPRESSED, None);
+        other.add_file(
+            crate::encode(&broken, crate::Compression::None).unwrap(),
+            Some(&drawer),
+        );
+        assert_eq!(other.render_failures["symbol-not-found"], 1);
+
+        stats.merge(other.clone());
+        assert_eq!(stats.files, 4);
+        assert_eq!(stats.parsed(), 3);
+        assert_eq!(stats.render_failures, other.render_failures);
+        assert_eq!(stats.layer_counts.values().sum::<usize>(), stats.parsed());
+    }
+
+    #[test]
+    fn test_unknown_canvas() {
+        // The height byte of the header, set to neither known canvas
+        let mut body = parse(Vec::from(RAW_FILE)).unwrap().to_bytes();
+        body[5] = 0x20;
+        let unknown = Payload::parse(&body).unwrap();
+        let bytes = crate::encode(&unknown, crate::Compression::None).unwrap();
+
+        let mut stats = CorpusStats::new();
+        stats.add_file(bytes, Some(&SymbolArtDrawer::new().with_raise_error(true)));
+        assert_eq!(stats.parsed(), 1);
+        assert_eq!(stats.canvases["unknown"], 1);
+        assert_eq!(stats.render_failures["unsupported-canvas"], 1);
+    }
+}